@@ -13,49 +13,219 @@ Flow:
   6. Hybrid output: combine top Markov words + top TF-IDF words → 8 words.
   7. For each of the 8 words, run a separate YouTube search (8 batches).  ◇
   8. De-duplicate, suppress disliked signals, return merged feed.
-  9. If history is empty, fall back to trending.
+  9. If the hybrid model finds nothing, fall back to plain title words
+     from the history, and only if there are none to the cold-start
+     (trending) searches.
 """
 
 from __future__ import annotations
 
+import json
+import math
 import random
-from typing import List, Dict, Any, Set
+from collections import Counter
+from dataclasses import dataclass, field
+from enum import Enum
+from typing import List, Dict, Any, Callable, Iterable, Optional, Set
 
-from algorithm.history import get_weighted_history, get_disliked_signals
+from algorithm.history import (
+    get_weighted_history, get_disliked_signals, preview_disliked_signals,
+    history_version, has_disliked_watches, get_disliked_history,
+)
 from algorithm.markov import MarkovChain
-from algorithm.tfidf import TFIDF
+from algorithm.scoring import Bm25Scorer, TermScorer, TfIdfScorer
+from algorithm.text import edit_distance, normalise_tag, stem, tokenize
+from algorithm.tfidf import TFIDF, cosine_similarity
 from algorithm.trending import get_trending
 from video.search import search_videos
-from config import FEED_CONFIG
+from config import FEED_CONFIG, DislikeMode
+
+
+# In-process {session_id: (history_version, {n: keywords or reason})}.
+# Filled by try_feed_keywords and ignored once the session's history
+# version moves on.
+_keyword_cache: Dict[str, tuple[int, Dict[int, Any]]] = {}
+
+# {session_id: (history_version, trained chain)}, the same way: the
+# chain is only retrained once the session's history has changed.
+_markov_cache: Dict[str, tuple[int, MarkovChain]] = {}
+# Chains trained by _session_chain so far; tests read it to check reuse
+_markov_builds = 0
+
+# Ranks the TF-IDF side of the hybrid; see set_term_scorer.
+_term_scorer: TermScorer = TfIdfScorer()
+
+# Supplies cold-start terms; see set_cold_start_provider.
+_cold_start_provider: Optional[Callable[[], List[str]]] = None
+
+
+def set_term_scorer(scorer: Optional[TermScorer]) -> None:
+    """
+    Rank the non-Markov side of the hybrid with `scorer` instead of
+    TF-IDF (None restores the default TfIdfScorer).  Cached keywords
+    are dropped, since they were ranked by the previous scorer.
+    """
+    global _term_scorer
+    _term_scorer = TfIdfScorer() if scorer is None else scorer
+    _keyword_cache.clear()
+
+
+def set_cold_start_provider(provider: Optional[Callable[[], List[str]]]) -> None:
+    """
+    Take cold-start search terms from `provider` (e.g. real trending
+    topics from a backend) instead of FEED_CONFIG.cold_start_terms;
+    None restores the configured list.  It is called on every cold
+    start and never while the history yields keywords.
+    """
+    global _cold_start_provider
+    _cold_start_provider = provider
+
+
+def cold_start_terms() -> List[str]:
+    """The terms build_feed searches when there are no feed keywords."""
+    if _cold_start_provider is not None:
+        return list(_cold_start_provider())
+    return list(FEED_CONFIG.cold_start_terms)
 
 
 # ---------------------------------------------------------------------------
 # Helpers
 # ---------------------------------------------------------------------------
 
-def _build_corpus(history: List[Dict[str, Any]]) -> List[tuple[str, float]]:
+def _build_corpus(
+    history: List[Dict[str, Any]],
+    attention: bool = False,
+) -> List[tuple[str, float]]:
     """
     Convert weighted history entries into (text, weight) documents
     for the language models.
 
     Each entry produces one document combining title + hashtags.
+    Both tokenisers treat "|" as whitespace.
+
+    If one channel accounts for more than channel_dominance_share of
+    the history, its documents are scaled by dominance_share / share,
+    so e.g. a channel with 90 % of watches keeps ~56 % of its weight.
+
+    Titles of videos tagged with a language have that language's stop
+    words removed here; the models only know the default list.
+
+    Unliked watches abandoned before FEED_CONFIG.abandon_ratio of the
+    video produce no document, so their words never become keywords;
+    they still count everywhere else the history is used.
+
+    With `attention` each weight is also scaled by the entry's
+    completion, floored at FEED_CONFIG.attention_floor (the TF-IDF
+    corpus under FEED_CONFIG.attention_weighted).
     """
+    channel_factor = _channel_damping(history)
     corpus = []
     for entry in history:
+        abandoned = entry.get("completion", 1.0) < FEED_CONFIG.abandon_ratio
+        if abandoned and not entry.get("liked"):
+            continue
+        title = entry["title"]
+        if entry.get("language"):
+            stop_words = FEED_CONFIG.stop_words_for(entry["language"])
+            title = " ".join(tokenize(title, stop_words))
         tags_str = " ".join(entry["hashtags"])
-        text = f"{entry['title']} {tags_str}"
-        corpus.append((text, entry["weight"]))
+        # "|" separates title from hashtags so phrase extraction never
+        # pairs the last title word with the first hashtag.
+        text = f"{title} | {tags_str}"
+        weight = entry["weight"] * channel_factor.get(entry.get("channel", ""), 1.0)
+        if attention:
+            weight *= max(entry.get("completion", 1.0), FEED_CONFIG.attention_floor)
+        corpus.append((text, weight))
     return corpus
 
 
+def _channel_damping(history: List[Dict[str, Any]]) -> Dict[str, float]:
+    """{channel: weight multiplier} for channels over the dominance share."""
+    counts = Counter(e.get("channel", "") for e in history if e.get("channel"))
+    total = len(history)
+    limit = FEED_CONFIG.channel_dominance_share
+    factors: Dict[str, float] = {}
+    for channel, count in counts.items():
+        share = count / total
+        if share > limit:
+            factors[channel] = limit / share
+    return factors
+
+
+class ColdStartReason(str, Enum):
+    """Why there are no keywords, so a UI can say something specific."""
+    NO_HISTORY = "no_history"            # Nothing watched yet
+    ALL_DISLIKED = "all_disliked"        # Every watch / word was disliked
+    NO_USABLE_WORDS = "no_usable_words"  # Watches, but no words to search
+
+
+class ColdStartError(Exception):
+    """Raised by try_feed_keywords; `reason` is a ColdStartReason."""
+
+    def __init__(self, reason: ColdStartReason):
+        super().__init__(reason.value)
+        self.reason = reason
+
+
+class Source(str, Enum):
+    """Which model(s) put a keyword forward."""
+    TFIDF = "tfidf"
+    MARKOV = "markov"
+    BOTH = "both"
+
+
+@dataclass
+class KeywordResult:
+    """
+    The hybrid model's chosen keywords plus where each one came from.
+
+    `terms` is the ranked output; `tfidf_terms` and `markov_terms` are
+    the members of `terms` each model scored, in the same order.
+    """
+    terms: List[str] = field(default_factory=list)
+    tfidf_terms: List[str] = field(default_factory=list)
+    markov_terms: List[str] = field(default_factory=list)
+
+    def source_of(self, term: str) -> Optional[Source]:
+        """Source of `term`, or None if it wasn't chosen."""
+        in_tfidf = term in self.tfidf_terms
+        in_markov = term in self.markov_terms
+        if in_tfidf and in_markov:
+            return Source.BOTH
+        if in_tfidf:
+            return Source.TFIDF
+        if in_markov:
+            return Source.MARKOV
+        return None
+
+
 def _hybrid_keywords(
     corpus: List[tuple[str, float]],
     disliked: Dict[str, float],
     n: int = FEED_CONFIG.num_output_words,
+    markov_fraction: Optional[float] = None,
+    diversify: Optional[bool] = None,
+    markov: Optional[MarkovChain] = None,
+    tfidf_corpus: Optional[List[tuple[str, float]]] = None,
 ) -> List[str]:
+    """The top `n` hybrid keywords (see _hybrid_keyword_result)."""
+    return _hybrid_keyword_result(
+        corpus, disliked, n, markov_fraction, diversify, markov, tfidf_corpus
+    ).terms
+
+
+def _hybrid_keyword_result(
+    corpus: List[tuple[str, float]],
+    disliked: Dict[str, float],
+    n: int = FEED_CONFIG.num_output_words,
+    markov_fraction: Optional[float] = None,
+    diversify: Optional[bool] = None,
+    markov: Optional[MarkovChain] = None,
+    tfidf_corpus: Optional[List[tuple[str, float]]] = None,
+) -> KeywordResult:
     """
     Run both models, merge their top words, filter disliked signals,
-    and return the top `n` keywords.
+    and return the top `n` keywords with the model each came from.
 
     Hybrid scoring:
       score(word) = markov_weight * markov_score_norm
@@ -63,19 +233,72 @@ def _hybrid_keywords(
 
     Both score lists are normalised to [0, 1] before combining so that
     neither model dominates due to scale differences.
+
+    `markov_fraction` (0.0-1.0) overrides the configured split for one
+    call: markov_weight = markov_fraction, tfidf_weight = 1 - that.
+    0.0 is pure TF-IDF and 1.0 pure Markov.
+
+    The ranking is deterministic for a given corpus: equal hybrid
+    scores are ordered alphabetically.
+
+    Every one of the `n` slots is filled when the corpus has enough
+    distinct words; `n <= 0` returns an empty result.
+
+    With FEED_CONFIG.stemming, words from both models and the disliked
+    penalties are matched on their stems ("recipes" == "recipe"), and
+    the surface form seen by TF-IDF is what gets returned.
+
+    `diversify` (default FEED_CONFIG.diversify) replaces the Markov
+    side's global transition totals with walks seeded round-robin from
+    the top TF-IDF words, and caps the keywords drawn from any one
+    document (see _select_diverse), so a viewer with several interests
+    isn't handed a single-topic query.
+
+    With FEED_CONFIG.markov_walk (and no diversify) the Markov side is
+    one random walk from a TF-IDF-score-weighted seed (_sampled_walk),
+    so a strong second interest sometimes leads.
+
+    `markov` is a chain already trained on `corpus` (see _session_chain);
+    without one a chain is trained here.  Its random generator decides
+    the walk, so a seeded chain (MarkovChain.with_seed, or
+    FEED_CONFIG.markov_seed) makes the keywords reproducible.
+
+    `tfidf_corpus`, the same documents reweighted (see _build_corpus's
+    `attention`), is what the TF-IDF side scores when given.
     """
-    # --- Markov ---
-    markov = MarkovChain()
-    markov.train(corpus)
-    markov_top = markov.top_transitions(top_n=40)  # (word, weight) pairs
+    stemming = FEED_CONFIG.stemming
+    if diversify is None:
+        diversify = FEED_CONFIG.diversify
+    if markov_fraction is None:
+        markov_w, tfidf_w = FEED_CONFIG.markov_weight, FEED_CONFIG.tfidf_weight
+    elif 0.0 <= markov_fraction <= 1.0:
+        markov_w, tfidf_w = markov_fraction, 1.0 - markov_fraction
+    else:
+        raise ValueError(f"markov_fraction must be within [0, 1], got {markov_fraction}")
 
-    # --- TF-IDF ---
-    tfidf = TFIDF()
-    tfidf.fit(corpus)
-    tfidf_top = tfidf.top_words(corpus, top_n=40)
+    if n <= 0:
+        return KeywordResult()
+
+    # --- TF-IDF (or whichever TermScorer is plugged in) ---
+    # Dislikes are graded penalties applied below, so nothing is
+    # blacklisted outright here.
+    tfidf_top = _term_scorer.score_terms(corpus if tfidf_corpus is None else tfidf_corpus)[:40]
+
+    # --- Markov ---
+    if markov is None:
+        markov = _new_chain()
+        markov.train(corpus)
+    if diversify:
+        seeds = [w for w, _ in tfidf_top if " " not in w][: FEED_CONFIG.diversify_seeds]
+        markov_top = _round_robin_walks(markov, seeds, top_n=40)
+    elif FEED_CONFIG.markov_walk:
+        markov_top = _sampled_walk(markov, tfidf_top, top_n=40)
+    else:
+        markov_top = markov.top_transitions(top_n=40)  # (word, weight) pairs
 
     # --- Normalise ---
     def _normalise(pairs: List[tuple[str, float]]) -> Dict[str, float]:
+        pairs = [(w, s) for w, s in pairs if math.isfinite(s)]
         if not pairs:
             return {}
         max_score = max(s for _, s in pairs)
@@ -86,36 +309,594 @@ def _hybrid_keywords(
     markov_norm = _normalise(markov_top)
     tfidf_norm = _normalise(tfidf_top)
 
+    # --- Near-miss dislikes, while terms are still surface forms ---
+    disliked = _fuzzy_dislikes(disliked, [*markov_norm, *tfidf_norm])
+
+    # --- Stemming: match on stems, report surface forms ---
+    surface: Dict[str, str] = {}
+    if stemming:
+        markov_norm = _rekey_by_stem(markov_norm, surface)
+        tfidf_norm = _rekey_by_stem(tfidf_norm, surface)    # TF-IDF spelling wins
+        disliked = _rekey_by_stem(disliked, {})
+
     # --- Combine ---
     all_words: Set[str] = set(markov_norm.keys()) | set(tfidf_norm.keys())
     hybrid_scores: Dict[str, float] = {}
     for word in all_words:
-        m_score = markov_norm.get(word, 0.0) * FEED_CONFIG.markov_weight
-        t_score = tfidf_norm.get(word, 0.0) * FEED_CONFIG.tfidf_weight
+        m_score = markov_norm.get(word, 0.0) * markov_w
+        t_score = tfidf_norm.get(word, 0.0) * tfidf_w
         hybrid_scores[word] = m_score + t_score
 
     # --- Filter disliked ---
-    # Words that appear in the disliked penalty dict with high penalty
-    # are suppressed entirely.  Lower-penalty words just get their score
-    # reduced proportionally.
-    filtered: Dict[str, float] = {}
-    max_penalty = max(disliked.values()) if disliked else 1.0
-    for word, score in hybrid_scores.items():
-        penalty = disliked.get(word, 0.0)
-        penalty_norm = penalty / max_penalty if max_penalty > 0 else 0.0
-        adjusted = score * (1.0 - min(penalty_norm, 0.99))
-        if adjusted > 0.01:     # Hard floor: completely suppressed words dropped
-            filtered[word] = adjusted
+    filtered = _apply_dislikes(hybrid_scores, disliked, FEED_CONFIG.dislike_mode)
 
     # --- Return top n ---
-    ranked = sorted(filtered.items(), key=lambda x: x[1], reverse=True)
-    return [word for word, _ in ranked[:n]]
+    # Equal scores go alphabetically: the candidates come out of a set,
+    # whose order changes between runs
+    ranked = [w for w, _ in sorted(filtered.items(), key=lambda x: (-x[1], x[0]))]
+    if diversify:
+        chosen = _select_diverse(ranked, corpus, n, FEED_CONFIG.diversify_max_per_video)
+    else:
+        chosen = ranked[:n]
+    result = KeywordResult()
+    for word in chosen:
+        term = surface.get(word, word)
+        result.terms.append(term)
+        if tfidf_w > 0 and tfidf_norm.get(word, 0.0) > 0:
+            result.tfidf_terms.append(term)
+        if markov_w > 0 and markov_norm.get(word, 0.0) > 0:
+            result.markov_terms.append(term)
+    return result
+
+
+def _history_keywords(
+    history: List[Dict[str, Any]],
+    disliked: Dict[str, float],
+    n: int,
+    markov: Optional[MarkovChain] = None,
+) -> List[str]:
+    """
+    Hybrid keywords for `history`, or its plain title words when the
+    models score nothing (e.g. a single short-watched video).  Empty
+    only when no watch has a usable, non-disliked word.
+    """
+    return (
+        _hybrid_keywords(_build_corpus(history), disliked, n=n, markov=markov,
+                         tfidf_corpus=_tfidf_corpus(history))
+        or _title_words(history, disliked, n)
+    )
+
+
+def _tfidf_corpus(history: List[Dict[str, Any]]) -> Optional[List[tuple[str, float]]]:
+    """The TF-IDF side's own corpus under FEED_CONFIG.attention_weighted, else None."""
+    if not FEED_CONFIG.attention_weighted:
+        return None
+    return _build_corpus(history, attention=True)
+
+
+def _new_chain() -> MarkovChain:
+    """An untrained chain whose walks follow FEED_CONFIG.markov_seed."""
+    return MarkovChain(rng=random.Random(FEED_CONFIG.markov_seed))
+
+
+def _session_chain(session_id: str, history: List[Dict[str, Any]]) -> MarkovChain:
+    """
+    The session's Markov chain trained on `history` (its current
+    weighted history), reused until the history version moves on.  As
+    with the keyword cache, decay is frozen at training time.
+
+    With FEED_CONFIG.avoid_disliked_sequences the chain also carries an
+    avoid chain trained the same way on the session's disliked videos
+    (see MarkovChain.avoid).
+    """
+    global _markov_builds
+    version = history_version(session_id)
+    cached = _markov_cache.get(session_id)
+    if cached is not None and cached[0] == version:
+        return cached[1]
+    markov = _new_chain()
+    markov.train(_build_corpus(history))
+    if FEED_CONFIG.avoid_disliked_sequences:
+        markov.avoid = MarkovChain(order=markov.order)
+        markov.avoid.train(_build_corpus(get_disliked_history(session_id)))
+    _markov_builds += 1
+    _markov_cache[session_id] = (version, markov)
+    return markov
+
+
+def _title_words(
+    history: List[Dict[str, Any]],
+    disliked: Dict[str, float],
+    n: int,
+) -> List[str]:
+    """
+    Up to `n` distinct title words, taken from the heaviest entries
+    first, with stop words and disliked words (on stems, when
+    FEED_CONFIG.stemming is on) left out.
+    """
+    normalise = _stem_key if FEED_CONFIG.stemming else (lambda t: t)
+    vocabulary = [w for e in history for w in tokenize(e["title"])]
+    blocked = {normalise(w) for w in _fuzzy_dislikes(disliked, vocabulary)}
+    words: List[str] = []
+    seen: Set[str] = set()
+    for entry in sorted(history, key=lambda e: e["weight"], reverse=True):
+        stop_words = FEED_CONFIG.stop_words_for(entry.get("language"))
+        for word in tokenize(entry["title"], stop_words, min_length=3):
+            key = normalise(word)
+            if key in blocked or key in seen:
+                continue
+            seen.add(key)
+            words.append(word)
+            if len(words) >= n:
+                return words
+    return words
+
+
+def _title_phrases(history: List[Dict[str, Any]], min_count: int) -> Dict[str, int]:
+    """
+    {"word word": videos} for the adjacent title word pairs (stop words
+    removed) that appear in at least `min_count` videos, each video
+    counted once per pair.
+    """
+    counts: Counter = Counter()
+    for entry in history:
+        stop_words = FEED_CONFIG.stop_words_for(entry.get("language"))
+        tokens = tokenize(entry["title"], stop_words, min_length=3)
+        counts.update({f"{a} {b}" for a, b in zip(tokens, tokens[1:])})
+    return {p: c for p, c in counts.items() if c >= max(min_count, 1)}
+
+
+def _join_phrases(
+    ranked: List[str],
+    phrases: Dict[str, int],
+    disliked: Dict[str, float],
+    n: int,
+) -> List[str]:
+    """
+    Walk `ranked` keywords best first, replacing a word with a phrase
+    from `phrases` when the phrase's other word is also ranked; the
+    phrase takes the better word's slot and uses up both words.  The
+    most frequent phrase wins when a word has several.  Phrases with a
+    disliked word are never used.  Returns up to `n` terms.
+    """
+    available = set(ranked)
+    by_word: Dict[str, List[str]] = {}
+    for phrase in sorted(phrases, key=lambda p: (-phrases[p], p)):
+        parts = phrase.split()
+        if any(part in disliked for part in parts):
+            continue
+        for part in parts:
+            by_word.setdefault(part, []).append(phrase)
+
+    terms: List[str] = []
+    used: Set[str] = set()
+    for word in ranked:
+        if len(terms) >= n:
+            break
+        if word in used:
+            continue
+        phrase = next(
+            (
+                p for p in by_word.get(word, [])
+                if all(part in available and part not in used for part in p.split())
+            ),
+            None,
+        )
+        if phrase is None:
+            terms.append(word)
+            used.add(word)
+        else:
+            terms.append(phrase)
+            used.update(phrase.split())
+    return terms
+
+
+def _round_robin_walks(
+    markov: MarkovChain,
+    seeds: List[str],
+    top_n: int = 40,
+) -> List[tuple[str, float]]:
+    """
+    Walk the chain from each seed in turn, one step per seed per round,
+    always taking the heaviest follower not already collected.  Returns
+    (word, score) pairs in collection order with scores falling from
+    top_n, so the first word of every seed's walk outranks the second.
+    A walk stops at a dead end; the rest carry on.
+    """
+    seen: List[str] = []
+    heads = list(dict.fromkeys(s.lower() for s in seeds))
+    for seed in heads:
+        if len(seen) < top_n:
+            seen.append(seed)
+    while heads and len(seen) < top_n:
+        alive = []
+        for word in heads:
+            step = next((w for w in markov.next_words(word) if w not in seen), None)
+            if step is None:
+                continue
+            seen.append(step)
+            alive.append(step)
+            if len(seen) >= top_n:
+                break
+        heads = alive
+    return [(w, float(top_n - i)) for i, w in enumerate(seen)]
+
+
+def _sampled_walk(
+    markov: MarkovChain,
+    tfidf_top: List[tuple[str, float]],
+    top_n: int = 40,
+) -> List[tuple[str, float]]:
+    """
+    One walk collecting up to `top_n` distinct words, started from one
+    of the first markov_walk_seeds single-word TF-IDF terms picked in
+    proportion to its score and restarted from another of them at each
+    dead end or loop (see MarkovChain.generate).  Returns (word, score)
+    pairs in walk order, scores falling from top_n.
+    """
+    words = [(w, s) for w, s in tfidf_top if " " not in w]
+    seeds = dict(words[: FEED_CONFIG.markov_walk_seeds])
+    walk = markov.generate(top_n, restart_seeds=list(seeds), seed_scores=seeds)
+    return [(w, float(top_n - i)) for i, w in enumerate(walk)]
+
+
+def _select_diverse(
+    ranked: List[str],
+    corpus: List[tuple[str, float]],
+    n: int,
+    max_per_doc: int,
+) -> List[str]:
+    """
+    Take terms from `ranked` (best first) until `n` are chosen, skipping
+    any whose main document - the heaviest one containing it - has
+    already supplied `max_per_doc`.  Skipped terms fill leftover slots
+    in rank order, so `n` is still met whenever `ranked` is long enough.
+    """
+    normalise = _stem_key if FEED_CONFIG.stemming else (lambda t: t)
+    docs = [
+        (weight, [f" {normalise(' '.join(tokenize(seg)))} " for seg in text.split("|")])
+        for text, weight in corpus
+    ]
+
+    def _main_doc(term: str) -> Optional[int]:
+        needle = f" {term} "
+        best = None
+        for i, (weight, segments) in enumerate(docs):
+            if any(needle in seg for seg in segments):
+                if best is None or weight > docs[best][0]:
+                    best = i
+        return best
+
+    chosen: List[str] = []
+    skipped: List[str] = []
+    per_doc: Counter = Counter()
+    for term in ranked:
+        if len(chosen) >= n:
+            break
+        doc = _main_doc(term)
+        if doc is not None and per_doc[doc] >= max_per_doc:
+            skipped.append(term)
+            continue
+        per_doc[doc] += 1
+        chosen.append(term)
+    chosen.extend(skipped[: n - len(chosen)])
+    order = {t: i for i, t in enumerate(ranked)}
+    return sorted(chosen, key=order.__getitem__)
+
+
+def _apply_dislikes(
+    scores: Dict[str, float],
+    disliked: Dict[str, float],
+    mode: DislikeMode,
+) -> Dict[str, float]:
+    """
+    Adjust hybrid scores (in [0, 1]) by the disliked penalties, each
+    taken relative to the largest penalty.
+
+    HARD_BLOCK: a word's score is scaled by (1 - relative_penalty); the
+    most-disliked words, anything scaled below 0.01 and any phrase
+    containing a disliked word are dropped.
+
+    PENALIZE: dislike_penalty_weight * relative_penalty is subtracted
+    instead (a phrase takes its worst word's penalty), and only terms
+    whose net score is no longer positive are dropped.  One disliked
+    "viral pasta" video then can't remove "pasta" for a pasta fan.
+    """
+    max_penalty = max(disliked.values()) if disliked else 0.0
+
+    def _relative(word: str) -> float:
+        if max_penalty <= 0:
+            return 0.0
+        return disliked.get(word, 0.0) / max_penalty
+
+    adjusted: Dict[str, float] = {}
+    for word, score in scores.items():
+        parts = word.split()
+        if mode is DislikeMode.PENALIZE:
+            penalty = max(_relative(p) for p in parts) if len(parts) > 1 else _relative(word)
+            net = score - FEED_CONFIG.dislike_penalty_weight * penalty
+            if net > 0:
+                adjusted[word] = net
+            continue
+
+        if len(parts) > 1 and any(p in disliked for p in parts):
+            continue            # Phrases containing a disliked word are dropped
+        penalty = _relative(word)
+        if penalty >= 0.99:
+            continue            # The most-disliked words are removed outright
+        net = score * (1.0 - penalty)
+        if net > 0.01:          # Hard floor: nearly suppressed words dropped too
+            adjusted[word] = net
+    return adjusted
+
+
+# Shorter words only match a dislike exactly under fuzzy_blacklist
+_FUZZY_MIN_LENGTH = 5
+
+
+def _closest_dislike(word: str, disliked: Dict[str, float], max_distance: int) -> Optional[str]:
+    """The disliked word `word` is a near-miss of, if any (see _fuzzy_dislikes)."""
+    bare = word[:-2] if word.endswith("'s") else word
+    if bare in disliked:
+        return bare
+    if len(bare) < _FUZZY_MIN_LENGTH or max_distance <= 0:
+        return None
+    best = None
+    for other in disliked:
+        if len(other) < _FUZZY_MIN_LENGTH or " " in other:
+            continue
+        distance = edit_distance(bare, other, limit=max_distance)
+        if distance <= max_distance:
+            rank = (distance, -disliked[other], other)
+            if best is None or rank < best:
+                best = rank
+    return best[2] if best else None
+
+
+def _fuzzy_dislikes(disliked: Dict[str, float], vocabulary: Iterable[str]) -> Dict[str, float]:
+    """
+    With FEED_CONFIG.fuzzy_blacklist, `disliked` plus every word of
+    `vocabulary` (terms about to be ranked) that is a near-miss of a
+    disliked word - "carbonaraa" or "carbonara's" for "carbonara" -
+    carrying that word's penalty.  Otherwise `disliked` unchanged.
+    """
+    if not FEED_CONFIG.fuzzy_blacklist or not disliked:
+        return disliked
+    max_distance = min(max(FEED_CONFIG.fuzzy_max_distance, 0), 2)
+    expanded = dict(disliked)
+    for term in vocabulary:
+        for word in term.split():
+            if word in expanded:
+                continue
+            match = _closest_dislike(word, disliked, max_distance)
+            if match is not None:
+                expanded[word] = disliked[match]
+    return expanded
+
+
+def _stem_key(term: str) -> str:
+    return " ".join(stem(part) for part in term.split())
+
+
+def _rekey_by_stem(scores: Dict[str, float], surface: Dict[str, str]) -> Dict[str, float]:
+    """
+    Collapse {word: score} onto stem keys, keeping the highest score per
+    stem and recording stem -> word in `surface`.
+    """
+    merged: Dict[str, float] = {}
+    for word, score in scores.items():
+        key = _stem_key(word)
+        if score >= merged.get(key, float("-inf")):
+            surface[key] = word
+        merged[key] = max(merged.get(key, score), score)
+    return merged
+
+
+def _term_sources(history: List[Dict[str, Any]], terms: List[str]) -> Dict[str, List[str]]:
+    """
+    {term: [video_id, ...]} naming the history entries whose title or
+    hashtags contain each term.  A phrase must appear as adjacent words
+    in the title or within one hashtag.  Matches on stems when
+    FEED_CONFIG.stemming is on.
+    """
+    normalise = _stem_key if FEED_CONFIG.stemming else (lambda t: t)
+
+    def _segments(entry: Dict[str, Any]) -> List[List[str]]:
+        parts = [entry["title"], *entry["hashtags"]]
+        return [normalise(" ".join(tokenize(p))).split() for p in parts]
+
+    docs = [(entry["video_id"], _segments(entry)) for entry in history]
+    sources: Dict[str, List[str]] = {}
+    for term in terms:
+        words = normalise(" ".join(tokenize(term))).split()
+        size = len(words)
+        sources[term] = [
+            vid for vid, segments in docs
+            if any(
+                seg[i : i + size] == words
+                for seg in segments
+                for i in range(len(seg) - size + 1)
+            )
+        ]
+    return sources
+
+
+def _similar_in_history(
+    history: List[Dict[str, Any]],
+    video_id: str,
+    n: int = 5,
+) -> List[str]:
+    """
+    Return up to `n` video_ids from `history` whose TF-IDF vectors are
+    most cosine-similar to `video_id`'s.  Uses the same corpus documents
+    as the feed, so disliked videos (absent from history) are never
+    candidates.  Empty if `video_id` isn't in history.
+    """
+    corpus = _build_corpus(history)
+    tfidf = TFIDF(
+        include_bigrams=FEED_CONFIG.tfidf_include_bigrams,
+        smooth_idf=FEED_CONFIG.tfidf_smooth_idf,
+    )
+    tfidf.fit(corpus)
+    vectors = [tfidf.score_document(text) for text, _ in corpus]
+
+    target = next(
+        (vec for entry, vec in zip(history, vectors) if entry["video_id"] == video_id),
+        None,
+    )
+    if target is None:
+        return []
+
+    best: Dict[str, float] = {}
+    for entry, vec in zip(history, vectors):
+        vid = entry["video_id"]
+        if vid == video_id:
+            continue
+        best[vid] = max(best.get(vid, 0.0), cosine_similarity(target, vec))
+
+    ranked = sorted(best.items(), key=lambda x: (-x[1], x[0]))
+    return [vid for vid, score in ranked[:n] if score > 0]
+
+
+def _cooccurring_tags(
+    history: List[Dict[str, Any]],
+    tag: str,
+    disliked: Dict[str, float],
+    n: int = 5,
+) -> List[tuple[str, int]]:
+    """
+    [(other_tag, count), ...] for the tags that appear on the same
+    videos as `tag`, most frequent first.  Each video counts once per
+    tag; disliked tags are never returned.  Empty if `tag` is unseen.
+    Tags are matched in the dislikes' form (normalise_tag), "#" dropped.
+    """
+    tag = normalise_tag(tag)
+    counts: Counter = Counter()
+    for entry in history:
+        tags = {normalise_tag(t) for t in entry["hashtags"]} - {""}
+        if tag not in tags:
+            continue
+        counts.update(t for t in tags if t != tag and t not in disliked)
+
+    ranked = sorted(counts.items(), key=lambda x: (-x[1], x[0]))
+    return ranked[:n]
+
+
+def _cooccurrence_graph(
+    history: List[Dict[str, Any]],
+    disliked: Dict[str, float],
+) -> List[tuple[str, str, int]]:
+    """
+    Undirected edges (tag_a, tag_b, count) for every pair of tags seen
+    on the same video, counting each video once per pair.  Disliked
+    tags are left out as nodes.  Each edge has tag_a < tag_b, and edges
+    are in lexicographic (tag_a, tag_b) order so the output is stable.
+    Tags are in the dislikes' form (normalise_tag), "#" dropped.
+    """
+    counts: Counter = Counter()
+    for entry in history:
+        tags = {normalise_tag(t) for t in entry["hashtags"]} - {""}
+        tags = sorted(tags - disliked.keys())
+        counts.update(
+            (a, b) for i, a in enumerate(tags) for b in tags[i + 1 :]
+        )
+    return [(a, b, count) for (a, b), count in sorted(counts.items())]
 
 
 # ---------------------------------------------------------------------------
 # Batch search  (◇ feature)
 # ---------------------------------------------------------------------------
 
+def _tfidf_profile(
+    history: List[Dict[str, Any]],
+    surface: Optional[Dict[str, str]] = None,
+) -> Dict[str, float]:
+    """
+    {term: score} for every term TF-IDF finds in the history, scaled so
+    the strongest is 1.0.  Keyed by stem when FEED_CONFIG.stemming is on,
+    recording stem -> word in `surface` if given.
+    """
+    corpus = _build_corpus(history)
+    tfidf = TFIDF(
+        include_bigrams=FEED_CONFIG.tfidf_include_bigrams,
+        stemming=FEED_CONFIG.stemming,
+        smooth_idf=FEED_CONFIG.tfidf_smooth_idf,
+        workers=FEED_CONFIG.tfidf_workers,
+    )
+    tfidf.fit(corpus)
+    top = tfidf.top_words(corpus, top_n=FEED_CONFIG.tfidf_max_features)
+    best = max((s for _, s in top), default=0.0)
+    if best <= 0:
+        return {}
+    profile = {w: s / best for w, s in top}
+    if not FEED_CONFIG.stemming:
+        return profile
+    return _rekey_by_stem(profile, {} if surface is None else surface)
+
+
+def _visible_profile(session_id: str, history: List[Dict[str, Any]]) -> Dict[str, float]:
+    """
+    _tfidf_profile of `history` keyed by surface form, without the
+    session's disliked words (fuzzy-matched when enabled) or phrases
+    containing one.
+    """
+    normalise = _stem_key if FEED_CONFIG.stemming else (lambda t: t)
+    surface: Dict[str, str] = {}
+    profile = _tfidf_profile(history, surface)
+    shown = [surface.get(term, term) for term in profile]
+    blocked = {normalise(w) for w in _fuzzy_dislikes(get_disliked_signals(session_id), shown)}
+    return {
+        surface.get(term, term): score for term, score in profile.items()
+        if not any(part in blocked for part in term.split())
+    }
+
+
+def _candidate_terms(
+    profile: Dict[str, float],
+    disliked: Dict[str, float],
+    candidate: Dict[str, Any],
+) -> tuple[Dict[str, float], Set[str]]:
+    """
+    ({term: contribution}, disliked_terms) for the distinct terms in a
+    search result's title and hashtags that the profile or the dislikes
+    know about.  A term contributes its profile score, minus
+    dislike_penalty_weight times its relative penalty if disliked.
+    """
+    normalise = _stem_key if FEED_CONFIG.stemming else (lambda t: t)
+    terms: Set[str] = set()
+    for segment in [candidate.get("title", ""), *candidate.get("hashtags", [])]:
+        tokens = [normalise(t) for t in tokenize(segment, FEED_CONFIG.stop_words, min_length=3)]
+        terms.update(tokens)
+        terms.update(f"{a} {b}" for a, b in zip(tokens, tokens[1:]))
+
+    contributions = {t: profile[t] for t in terms if t in profile}
+    blocked: Set[str] = set()
+    max_penalty = max(disliked.values(), default=0.0)
+    if max_penalty > 0:
+        keyed = _rekey_by_stem(disliked, {}) if FEED_CONFIG.stemming else disliked
+        blocked = {t for t in terms if t in keyed}
+        for t in blocked:
+            contributions[t] = contributions.get(t, 0.0) - (
+                FEED_CONFIG.dislike_penalty_weight * keyed[t] / max_penalty
+            )
+    return contributions, blocked
+
+
+def _score_candidate(
+    profile: Dict[str, float],
+    disliked: Dict[str, float],
+    candidate: Dict[str, Any],
+) -> float:
+    """
+    Relevance of a search result: the profile scores of the distinct
+    terms in its title and hashtags, minus dislike_penalty_weight times
+    the relative penalty of each disliked word it contains.  May be
+    negative.
+    """
+    contributions, _ = _candidate_terms(profile, disliked, candidate)
+    return sum(contributions.values())
+
+
 def _batch_search(keywords: List[str]) -> List[Dict[str, Any]]:
     """
     For each keyword, run an independent YouTube search and collect
@@ -153,18 +934,15 @@ def build_feed(session_id: str) -> List[Dict[str, Any]]:
     Main entry point.  Returns an ordered list of video dicts ready
     to be rendered in the feed template.
     """
-    history = get_weighted_history(session_id)
-
-    # Empty history → trending fallback
-    if not history:
-        return get_trending(max_results=FEED_CONFIG.trending_fallback_count)
-
-    disliked = get_disliked_signals(session_id)
-    corpus = _build_corpus(history)
-    keywords = _hybrid_keywords(corpus, disliked, n=FEED_CONFIG.num_output_words)
+    keywords = feed_keywords(session_id, n=FEED_CONFIG.num_output_words)
 
+    # No usable vocabulary at all (empty history, or every word
+    # disliked) → cold-start trending searches
     if not keywords:
-        return get_trending(max_results=FEED_CONFIG.trending_fallback_count)
+        return get_trending(
+            max_results=FEED_CONFIG.trending_fallback_count,
+            terms=cold_start_terms(),
+        )
 
     feed = _batch_search(keywords)
 
@@ -177,3 +955,313 @@ def build_feed(session_id: str) -> List[Dict[str, Any]]:
     feed = [v for group in groups for v in group]
 
     return feed
+
+
+def rank_candidates(
+    session_id: str,
+    candidates: List[Dict[str, Any]],
+) -> List[tuple[Dict[str, Any], float]]:
+    """
+    Re-rank search results (video dicts with "title" and "hashtags")
+    against the session's TF-IDF profile, best first, as
+    (video, score) pairs.  Ties keep their original order.
+    """
+    history = get_weighted_history(session_id)
+    profile = _tfidf_profile(history) if history else {}
+    disliked = get_disliked_signals(session_id)
+    scored = [(video, _score_candidate(profile, disliked, video)) for video in candidates]
+    return sorted(scored, key=lambda pair: pair[1], reverse=True)
+
+
+def explain_candidate(session_id: str, candidate: Dict[str, Any]) -> Dict[str, Any]:
+    """
+    "Why was I recommended this": the scoring behind rank_candidates
+    for one video dict, as
+
+        {"terms":   [(term, contribution), ...],   # highest first
+         "blocked": [term, ...]}                   # disliked terms it contains
+
+    The contributions sum to the candidate's rank_candidates score;
+    disliked terms contribute negatively.
+    """
+    history = get_weighted_history(session_id)
+    surface: Dict[str, str] = {}
+    profile = _tfidf_profile(history, surface) if history else {}
+    disliked = get_disliked_signals(session_id)
+    contributions, blocked = _candidate_terms(profile, disliked, candidate)
+
+    def _shown(term: str) -> str:
+        return surface.get(term, term)
+
+    ranked = sorted(contributions.items(), key=lambda x: (-x[1], x[0]))
+    return {
+        "terms": [(_shown(term), score) for term, score in ranked],
+        "blocked": sorted(_shown(term) for term in blocked),
+    }
+
+
+def recent_top_words(session_id: str, window: int = 10, n: int = 5) -> List[str]:
+    """
+    "Lately you've been into": the top `n` TF-IDF terms over only the
+    newest `window` non-disliked watches (all of them if there are
+    fewer).  Disliked words, and phrases containing one, are left out.
+    """
+    if window <= 0 or n <= 0:
+        return []
+    history = get_weighted_history(session_id)[:window]     # Newest first
+    if not history:
+        return []
+    ranked = sorted(_visible_profile(session_id, history).items(), key=lambda x: (-x[1], x[0]))
+    return [term for term, _ in ranked[:n]]
+
+
+def profile_vector(session_id: str) -> Dict[str, float]:
+    """
+    The session's TF-IDF profile as {term: score}: what rank_candidates
+    matches search results against, for storing, comparing or feeding
+    to another model.  Scores are scaled so the strongest term is 1.0;
+    disliked words, and phrases containing one, are left out.  Sorted
+    by score and cut to n, it gives recent_top_words over the whole
+    history.  Empty with no non-disliked watches.
+    """
+    history = get_weighted_history(session_id)
+    return _visible_profile(session_id, history) if history else {}
+
+
+def bm25_top_words(
+    session_id: str,
+    n: int = 5,
+    k1: float = 1.5,
+    b: float = 0.75,
+) -> List[str]:
+    """
+    The top `n` terms of the session's liked-or-neutral watches ranked
+    by BM25 (see scoring.Bm25Scorer) instead of TF-IDF.  Disliked
+    videos never enter the corpus, and disliked words, and phrases
+    containing one, are left out, as in recent_top_words.
+    """
+    if n <= 0:
+        return []
+    history = get_weighted_history(session_id)
+    if not history:
+        return []
+    disliked = get_disliked_signals(session_id)
+    ranked = Bm25Scorer(k1=k1, b=b).score_terms(_build_corpus(history), set(disliked))
+    return [term for term, _ in ranked[:n]]
+
+
+def interest_drift(session_id: str, recent_window: int = 10) -> float:
+    """
+    How far the session's tastes have moved: 1 - cosine similarity of
+    the TF-IDF profile of the newest `recent_window` non-disliked
+    watches and that of every watch before them.  0.0 means the same
+    interests, 1.0 nothing in common (e.g. cooking -> gaming).  0.0
+    when either side has no watches.
+    """
+    if recent_window <= 0:
+        return 0.0
+    history = get_weighted_history(session_id)         # Newest first
+    recent, earlier = history[:recent_window], history[recent_window:]
+    if not recent or not earlier:
+        return 0.0
+    similarity = cosine_similarity(_tfidf_profile(recent), _tfidf_profile(earlier))
+    return 1.0 - similarity
+
+
+def related_videos(session_id: str, video_id: str, n: int = 5) -> List[str]:
+    """"More like this": ids of the watched videos closest to `video_id`."""
+    history = get_weighted_history(session_id)
+    return _similar_in_history(history, video_id, n)
+
+
+def cooccurring_tags(session_id: str, tag: str, n: int = 5) -> List[tuple[str, int]]:
+    """Tags most often seen alongside `tag` in the session's liked-or-neutral watches."""
+    history = get_weighted_history(session_id)
+    disliked = get_disliked_signals(session_id)
+    return _cooccurring_tags(history, tag, disliked, n)
+
+
+def cooccurrence_graph(session_id: str) -> List[tuple[str, str, int]]:
+    """Interest graph: tag pairs sharing liked-or-neutral watches (see _cooccurrence_graph)."""
+    history = get_weighted_history(session_id)
+    disliked = get_disliked_signals(session_id)
+    return _cooccurrence_graph(history, disliked)
+
+
+def explain_keywords(
+    session_id: str,
+    n: int = FEED_CONFIG.num_output_words,
+) -> Dict[str, List[str]]:
+    """
+    Debugging aid: the keywords the feed would search for, each mapped
+    to the ids of the watched videos that contributed it.  Disliked
+    videos are never listed since they are not part of the history.
+    """
+    keywords = feed_keywords(session_id, n=n)
+    if not keywords:
+        return {}
+    return _term_sources(get_weighted_history(session_id), keywords)
+
+
+def keywords_as_json(session_id: str, n: int = FEED_CONFIG.num_output_words) -> str:
+    """
+    The feed's search terms as JSON for external tools, e.g.
+
+        {"terms": ["pasta", ...], "generated_from": {"pasta": ["vid1"]},
+         "cold_start": false, "cold_start_reason": null}
+
+    On a cold start `terms` holds cold_start_terms(),
+    `generated_from` is empty and the reason is a ColdStartReason value.
+    """
+    try:
+        terms = try_feed_keywords(session_id, n)
+        reason = None
+        sources = _term_sources(get_weighted_history(session_id), terms) if terms else {}
+    except ColdStartError as cold:
+        terms = cold_start_terms()
+        reason = cold.reason.value
+        sources = {}
+    return json.dumps({
+        "terms": terms,
+        "generated_from": sources,
+        "cold_start": reason is not None,
+        "cold_start_reason": reason,
+    })
+
+
+def feed_keywords(session_id: str, n: int = FEED_CONFIG.num_output_words) -> List[str]:
+    """
+    The keywords build_feed would currently search for; empty on a cold
+    start, where build_feed searches cold_start_terms() instead (see
+    try_feed_keywords for the reason).
+    """
+    try:
+        return try_feed_keywords(session_id, n)
+    except ColdStartError:
+        return []
+
+
+def try_feed_keywords(session_id: str, n: int = FEED_CONFIG.num_output_words) -> List[str]:
+    """
+    Like feed_keywords, but raises ColdStartError saying why there are
+    no keywords instead of returning an empty list.  `n <= 0` simply
+    returns [].
+
+    Cached per session until its history changes (any recorded watch,
+    import, clear or undislike), so repeated calls between watches
+    skip rebuilding the corpus and both models.  Decay is evaluated
+    when the keywords are computed, not on every cache hit.
+    """
+    version = history_version(session_id)
+    cached_version, by_n = _keyword_cache.get(session_id, (None, {}))
+    if cached_version != version:
+        by_n = {}
+        _keyword_cache[session_id] = (version, by_n)
+
+    if n not in by_n:
+        by_n[n] = _keywords_or_reason(session_id, n)
+    if isinstance(by_n[n], ColdStartReason):
+        raise ColdStartError(by_n[n])
+    return list(by_n[n])
+
+
+def _keywords_or_reason(session_id: str, n: int) -> Any:
+    if n <= 0:
+        return []
+    history = get_weighted_history(session_id)
+    if not history:
+        if has_disliked_watches(session_id):
+            return ColdStartReason.ALL_DISLIKED
+        return ColdStartReason.NO_HISTORY
+    disliked = get_disliked_signals(session_id)
+    keywords = _history_keywords(history, disliked, n, _session_chain(session_id, history))
+    if keywords:
+        return keywords
+    if _title_words(history, {}, 1):
+        return ColdStartReason.ALL_DISLIKED
+    return ColdStartReason.NO_USABLE_WORDS
+
+
+def feed_keyword_result(
+    session_id: str,
+    n: int = FEED_CONFIG.num_output_words,
+) -> KeywordResult:
+    """
+    The hybrid model's keywords for the session with the model each
+    came from (see KeywordResult.source_of), e.g. for a debugging view
+    of why a term was picked.  Computed afresh on every call.
+
+    Empty on a cold start, and when the models score nothing and
+    feed_keywords falls back to plain title words.
+    """
+    if n <= 0:
+        return KeywordResult()
+    history = get_weighted_history(session_id)
+    if not history:
+        return KeywordResult()
+    return _hybrid_keyword_result(
+        _build_corpus(history),
+        get_disliked_signals(session_id),
+        n,
+        markov=_session_chain(session_id, history),
+        tfidf_corpus=_tfidf_corpus(history),
+    )
+
+
+def clear_keyword_cache() -> None:
+    """Drop every cached keyword list and chain (tests use this between cases)."""
+    _keyword_cache.clear()
+    _markov_cache.clear()
+
+
+def feed_keyword_phrases(session_id: str, n: int = FEED_CONFIG.num_output_words) -> List[str]:
+    """
+    Like feed_keywords, but words that keep appearing side by side in
+    the watched titles (FEED_CONFIG.phrase_min_count videos or more)
+    come back joined, e.g. "pasta carbonara", so a search keeps them
+    together.  A phrase fills one of the `n` slots.
+
+    Phrases are only formed from words the hybrid model still ranks,
+    after dislikes, and a phrase containing any disliked word is
+    dropped: its words may still appear on their own if they survived.
+    Empty on a cold start.
+    """
+    if n <= 0:
+        return []
+    history = get_weighted_history(session_id)
+    if not history:
+        return []
+    disliked = get_disliked_signals(session_id)
+    markov = _session_chain(session_id, history)
+    ranked = _history_keywords(history, disliked, max(n * 2, 40), markov)
+    phrases = _title_phrases(history, FEED_CONFIG.phrase_min_count)
+    return _join_phrases(ranked, phrases, disliked, n)
+
+
+def preview_dislike(
+    session_id: str,
+    video_id: str,
+    n: int = FEED_CONFIG.num_output_words,
+) -> List[str]:
+    """
+    The keywords the feed would use if `video_id` were disliked, so a UI
+    can show what a dislike would remove.  Nothing is written.
+    """
+    history = [e for e in get_weighted_history(session_id) if e["video_id"] != video_id]
+    if not history:
+        return []
+    disliked = preview_disliked_signals(session_id, video_id)
+    return _history_keywords(history, disliked, n)
+
+
+def next_word_candidates(session_id: str, word: str, n: int = 10) -> List[str]:
+    """
+    "Related terms" autocomplete: up to `n` words that follow `word` in
+    the session's history, with disliked words left out.  The chain is
+    shared with the feed keywords and retrained only when the history
+    changes.
+    """
+    history = get_weighted_history(session_id)
+    disliked = get_disliked_signals(session_id)
+    markov = _session_chain(session_id, history)
+    return [w for w in markov.next_words(word) if w not in disliked][:n]
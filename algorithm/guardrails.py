@@ -11,27 +11,100 @@ Guardrail logic:
 
   Attention span % = A / B
 
-  Discard if A < 5 seconds.
+  Discard if A < 5 seconds, or if A is over 24 hours / not a number
+  (a broken client report must not inflate the session total).
 
   Trigger break if:
     - attention% < 25% AND C > 8 minutes  (lots of short skips = unfocused)
     - C > 20 minutes  (hard cap regardless of attention)
+    - today's total watch time is over the optional daily limit
+      (this one survives breaks and only clears at midnight)
+    - attention% has been falling steadily this session (least-squares
+      slope <= -0.1 per watch over at least 5 watches), even if the
+      average is still fine
+    - most of this session's reports were skips under 5 seconds
+      (counted even though the skips themselves are not recorded)
 
   Break NEVER cuts a video.  It is flagged and served when the current
-  video ends (enforced client-side with player.js).
+  video ends (enforced client-side with player.js).  record_watch
+  reports RecordOutcome.BREAK_TRIGGERED only for the watch that first
+  tips the session over a limit, so a UI can react to that one.
 
-  Break length = f(H), scaled from 3 min (daytime) to 10 min (late night).
+  Once the break screen is shown the break is tracked server-side:
+  watches recorded while it is running are ignored, and the stats can
+  only be reset once it has elapsed.
+
+  Break length = f(H), scaled from 3 min (daytime) to 10 min (late night),
+  staying at the maximum from 11 PM through to 5 AM.
   Parents can override to 10 / 30 / 60 min presets.
+
+  Every break trigger, daily limit and discarded misclick is also noted
+  in an in-process event log (see events) shown on the parent
+  dashboard.  The log is observational only and never changes a
+  decision.
 """
 
 from __future__ import annotations
 
-from datetime import datetime, timezone, date
-from typing import Optional
+import bisect
+import json
+import math
+from dataclasses import dataclass, asdict
+from datetime import datetime, timedelta, timezone, date
+from enum import Enum
+from typing import Dict, List, Optional, Sequence
 
 from database.db import db
 from database.models import SessionStats, WatchEvent, ParentSettings
-from config import GUARDRAIL_CONFIG
+from algorithm.history import mark_history_changed, get_blacklisted_words, trim_history
+from config import GUARDRAIL_CONFIG, GuardrailConfig
+
+
+# ---------------------------------------------------------------------------
+# Event log
+# ---------------------------------------------------------------------------
+
+class GuardrailEventKind(str, Enum):
+    """What a GuardrailEvent records."""
+    BREAK = "break"                  # A break was triggered
+    DAILY_LIMIT = "daily_limit"      # A break was triggered by the daily limit
+    MISCLICK = "misclick"            # A watch too short to count was discarded
+
+
+@dataclass
+class GuardrailEvent:
+    """One entry in a session's event log, with the stats at that moment."""
+    kind: GuardrailEventKind
+    at: datetime                     # UTC
+    session_minutes: float           # Counted watch time this session
+    daily_minutes: float
+    attention: float                 # Completion of the watch that caused it
+    reason: str = ""
+
+
+# {session_id: events, oldest first}, at most event_log_max per session.
+# In-process like the feed's caches, so each worker keeps its own log and
+# it starts empty again after a restart.
+_event_log: Dict[str, List[GuardrailEvent]] = {}
+
+
+def _log_event(
+    session_id: str,
+    kind: GuardrailEventKind,
+    stats: SessionStats,
+    attention: float,
+    reason: str = "",
+) -> None:
+    log = _event_log.setdefault(session_id, [])
+    log.append(GuardrailEvent(
+        kind=kind,
+        at=datetime.utcnow(),
+        session_minutes=stats.total_watch_minutes,
+        daily_minutes=stats.daily_watch_minutes,
+        attention=attention,
+        reason=reason,
+    ))
+    del log[: -GUARDRAIL_CONFIG.event_log_max]
 
 
 # ---------------------------------------------------------------------------
@@ -54,10 +127,17 @@ def _reset_if_new_day(stats: SessionStats) -> None:
         last = last.replace(tzinfo=timezone.utc)
     today = datetime.now(timezone.utc).date()
     if last.date() < today:
-        stats.reset()
+        stats.reset_daily()
         db.session.commit()
 
 
+def _break_remaining(stats: SessionStats) -> float:
+    """Seconds left on the current break (0.0 if not on a break)."""
+    if stats.break_ends_at is None:
+        return 0.0
+    return max((stats.break_ends_at - datetime.utcnow()).total_seconds(), 0.0)
+
+
 def _get_parent_override(session_id: str) -> Optional[int]:
     ps = ParentSettings.query.filter_by(session_id=session_id).first()
     if ps and ps.break_override_seconds:
@@ -65,10 +145,190 @@ def _get_parent_override(session_id: str) -> Optional[int]:
     return None
 
 
+def _local_hour(session_id: str) -> int:
+    """
+    The viewer's local hour as of their latest recorded watch (see
+    record_watch's `hour`), or the current UTC hour with none on record.
+    """
+    latest = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .order_by(WatchEvent.timestamp.desc())
+        .first()
+    )
+    hour = getattr(latest, "hour_of_day", None)
+    if isinstance(hour, int) and 0 <= hour <= 23:
+        return hour
+    return datetime.now(timezone.utc).hour
+
+
+def _session_watches(session_id: str, since: datetime) -> List[WatchEvent]:
+    """Watches recorded since the last reset, oldest first."""
+    return (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.timestamp >= since)
+        .order_by(WatchEvent.timestamp.asc())
+        .all()
+    )
+
+
+def _session_attention(session_id: str, since: datetime) -> List[float]:
+    """Completion ratios of the watches since the last reset, oldest first."""
+    return [ev.completion_ratio for ev in _session_watches(session_id, since)]
+
+
+def average_attention(
+    watches: Sequence[WatchEvent],
+    cfg: GuardrailConfig = GUARDRAIL_CONFIG,
+) -> float:
+    """Plain mean completion ratio (cfg.default_attention with no watches)."""
+    if not watches:
+        return cfg.default_attention
+    return sum(ev.completion_ratio for ev in watches) / len(watches)
+
+
+def weighted_attention(
+    watches: Sequence[WatchEvent],
+    cfg: GuardrailConfig = GUARDRAIL_CONFIG,
+) -> float:
+    """
+    Length-aware attention average.  Watches are grouped into the
+    cfg.attention_length_buckets (shorts / short / medium / long by
+    default); within a bucket each completion ratio is weighted by the
+    seconds actually watched, and the buckets are then averaged equally.
+
+    So ten fully-watched 30 s shorts don't drown out one documentary
+    watched for ten minutes, and within the documentary bucket ten
+    minutes counts for more than one.  cfg.default_attention with no
+    watches.
+    """
+    weighted: dict[int, float] = {}
+    seconds: dict[int, float] = {}
+    for ev in watches:
+        bucket = bisect.bisect_left(cfg.attention_length_buckets, ev.video_duration_seconds)
+        watched = min(ev.watch_time_seconds, ev.video_duration_seconds)
+        weighted[bucket] = weighted.get(bucket, 0.0) + ev.completion_ratio * watched
+        seconds[bucket] = seconds.get(bucket, 0.0) + watched
+
+    scores = [weighted[b] / seconds[b] for b in seconds if seconds[b] > 0]
+    if not scores:
+        return cfg.default_attention
+    return sum(scores) / len(scores)
+
+
+def ewma_attention(scores: Sequence[float], alpha: float = 0.3) -> float:
+    """
+    Exponentially weighted moving average of attention scores, oldest
+    first: each score moves the average `alpha` of the way towards it,
+    so the latest watches dominate.  alpha = 1.0 is just the last
+    score.  Returns 0.0 with no scores.
+    """
+    if not 0.0 < alpha <= 1.0:
+        raise ValueError(f"alpha must be within (0, 1], got {alpha}")
+    if not scores:
+        return 0.0
+    average = scores[0]
+    for score in scores[1:]:
+        average += alpha * (score - average)
+    return average
+
+
+def attention_trend(scores: Sequence[float]) -> float:
+    """
+    Least-squares slope of attention against watch number: roughly how
+    much completion changes per watch.  Negative means fading focus.
+    Returns 0.0 for fewer than two scores.
+    """
+    n = len(scores)
+    if n < 2:
+        return 0.0
+    mean_x = (n - 1) / 2
+    mean_y = sum(scores) / n
+    cov = sum((i - mean_x) * (y - mean_y) for i, y in enumerate(scores))
+    var = sum((i - mean_x) ** 2 for i in range(n))
+    return cov / var
+
+
+def _over_daily_limit(daily_minutes: float, cfg: GuardrailConfig) -> bool:
+    return (
+        cfg.daily_limit_minutes is not None
+        and daily_minutes >= cfg.daily_limit_minutes
+    )
+
+
+def evaluate_break(
+    total_minutes: float,
+    low_attention_minutes: float,
+    attention_pct: float,
+    cfg: GuardrailConfig = GUARDRAIL_CONFIG,
+    *,
+    daily_minutes: float = 0.0,
+    attention_scores: Sequence[float] = (),
+    low_streak: int = 0,
+    skipped: int = 0,
+    counted: int = 0,
+) -> tuple[bool, str]:
+    """
+    Pure break decision, separated from the database so any
+    GuardrailConfig (e.g. a stricter one for a younger child) can be
+    evaluated directly.
+
+    Returns (break_needed, reason).
+    """
+    hard_limit = cfg.hard_session_limit_minutes
+    low_att_limit = cfg.low_attention_session_minutes
+
+    if _over_daily_limit(daily_minutes, cfg):
+        return True, f"Daily limit ({cfg.daily_limit_minutes} min) reached."
+
+    if total_minutes > hard_limit:
+        return True, f"Hard session limit ({hard_limit} min) reached."
+
+    focus = attention_pct
+    if cfg.attention_ewma_alpha is not None and attention_scores:
+        focus = ewma_attention(attention_scores, cfg.attention_ewma_alpha)
+    if (
+        focus < cfg.low_attention_threshold
+        and low_attention_minutes > low_att_limit
+    ):
+        return True, (
+            f"Low attention ({focus:.0%} completion) for over "
+            f"{low_att_limit} min."
+        )
+
+    streak_limit = cfg.low_attention_streak_limit
+    if streak_limit > 0 and low_streak >= streak_limit:
+        return True, f"{low_streak} low-attention videos in a row."
+
+    attempts = skipped + counted
+    if 0 < cfg.skip_rate_min_attempts <= attempts:
+        rate = skipped / attempts
+        if rate >= cfg.skip_rate_limit:
+            return True, f"Skipped {skipped} of {attempts} videos this session."
+
+    if len(attention_scores) >= cfg.attention_trend_min_watches:
+        slope = attention_trend(attention_scores)
+        if slope <= cfg.attention_decline_slope:
+            return True, (
+                f"Attention falling ({slope:+.0%} completion per video) "
+                f"over the last {len(attention_scores)} videos."
+            )
+
+    return False, ""
+
+
 # ---------------------------------------------------------------------------
 # Public API
 # ---------------------------------------------------------------------------
 
+class RecordOutcome(str, Enum):
+    """What record_watch did with a watch."""
+    IGNORED = "ignored"                  # Not recorded (see "reason")
+    COUNTED = "counted"                  # Recorded; no new break
+    BREAK_TRIGGERED = "break_triggered"  # Recorded, and first to need a break
+
+
 def record_watch(
     session_id: str,
     video_id: str,
@@ -78,105 +338,465 @@ def record_watch(
     video_duration_seconds: float,
     liked: bool = False,
     disliked: bool = False,
+    channel: str = "",
+    language: Optional[str] = None,
+    hour: Optional[int] = None,
+    cfg: Optional[GuardrailConfig] = None,
 ) -> dict:
     """
     Record a watch event and update session stats.
 
+    `hour` is the viewer's local hour (0-23) at the end of the watch and
+    drives the break length, and the night_mode limits when cfg has
+    them; it is re-read on every call, so a session running past 6 PM
+    gets longer breaks from then on.  Without it the server's current
+    UTC hour is used.
+    `cfg` defaults to the global GUARDRAIL_CONFIG.
+
     Returns a dict:
         {
-            "recorded": bool,          # False if thrown out (< 5 sec)
+            "recorded": bool,          # False if thrown out (< 5 sec,
+                                       # > 24 h, on a break, or liked
+                                       # AND disliked)
             "break_needed": bool,      # True if a break should be triggered
             "break_seconds": int,      # How long the break should be
             "reason": str,             # Human-readable reason for break
+            "outcome": RecordOutcome,  # BREAK_TRIGGERED once per break
         }
+
+    break_needed stays True on every later watch until the break is
+    served; outcome is BREAK_TRIGGERED only on the first of them.
     """
-    # Discard very short watches
-    if watch_time_seconds < GUARDRAIL_CONFIG.min_watch_seconds:
+    cfg = cfg or GUARDRAIL_CONFIG
+    too_short = watch_time_seconds < cfg.min_watch_seconds
+
+    # Discard nonsensical reports (NaN fails every comparison, so check it)
+    if not too_short and (
+        not math.isfinite(watch_time_seconds) or watch_time_seconds > cfg.max_watch_seconds
+    ):
         return {"recorded": False, "break_needed": False,
-                "break_seconds": 0, "reason": "watch_too_short"}
+                "break_seconds": 0, "reason": "watch_too_long",
+                "outcome": RecordOutcome.IGNORED}
 
-    hour = datetime.now(timezone.utc).hour
+    stats = _get_or_create_stats(session_id)
+    _reset_if_new_day(stats)
+
+    # Watches reported while a break is running don't count
+    remaining = _break_remaining(stats)
+    if remaining > 0:
+        return {"recorded": False, "break_needed": True,
+                "break_seconds": math.ceil(remaining), "reason": "on_break",
+                "outcome": RecordOutcome.IGNORED}
+
+    if hour is None or not 0 <= hour <= 23:
+        hour = datetime.now(timezone.utc).hour
+    cfg = cfg.for_hour(hour)
+
+    # Discard very short watches, but count the skip towards the skip rate
+    if too_short:
+        stats.skipped_watches = (stats.skipped_watches or 0) + 1
+        db.session.commit()
+        skipped_pct = min(max(watch_time_seconds, 0.0) / max(video_duration_seconds, 1.0), 1.0)
+        _log_event(session_id, GuardrailEventKind.MISCLICK, stats, skipped_pct,
+                   "watch_too_short")
+        break_needed, reason = evaluate_break(
+            stats.total_watch_minutes,
+            stats.low_attention_minutes,
+            1.0,                # A skip is not an attention sample
+            cfg,
+            daily_minutes=stats.daily_watch_minutes,
+            skipped=stats.skipped_watches,
+            counted=stats.counted_watches or 0,
+        )
+        outcome = _flag_break(stats, break_needed, RecordOutcome.IGNORED)
+        if outcome is RecordOutcome.BREAK_TRIGGERED:
+            _log_break(session_id, stats, skipped_pct, reason, cfg)
+        return {
+            "recorded": False,
+            "break_needed": break_needed,
+            "break_seconds": (
+                cfg.break_length_for_hour(hour, _get_parent_override(session_id))
+                if break_needed else 0
+            ),
+            "reason": reason or "watch_too_short",
+            "outcome": outcome,
+        }
 
     # Persist the event
-    event = WatchEvent(
-        session_id=session_id,
-        video_id=video_id,
-        video_title=video_title,
-        video_hashtags=video_hashtags,
-        watch_time_seconds=watch_time_seconds,
-        video_duration_seconds=max(video_duration_seconds, 1.0),
-        liked=liked,
-        disliked=disliked,
-        hour_of_day=hour,
-    )
+    try:
+        event = WatchEvent.build(
+            session_id, video_id, watch_time_seconds, video_duration_seconds,
+            title=video_title,
+            hashtags=video_hashtags,
+            channel=channel,
+            language=language,
+            liked=liked,
+            disliked=disliked,
+            hour_of_day=hour,
+        )
+    except ValueError:
+        return {"recorded": False, "break_needed": False,
+                "break_seconds": 0, "reason": "invalid_watch",
+                "outcome": RecordOutcome.IGNORED}
     db.session.add(event)
 
     # Update session stats
-    stats = _get_or_create_stats(session_id)
-    _reset_if_new_day(stats)
-
     watch_minutes = watch_time_seconds / 60.0
     stats.total_watch_minutes += watch_minutes
+    stats.daily_watch_minutes += watch_minutes
+    stats.counted_watches = (stats.counted_watches or 0) + 1
 
-    attention_pct = watch_time_seconds / max(video_duration_seconds, 1.0)
-    if attention_pct < GUARDRAIL_CONFIG.low_attention_threshold:
+    # Capped at 100 % so a replayed video can't mask low attention elsewhere
+    attention_pct = min(watch_time_seconds / max(video_duration_seconds, 1.0), 1.0)
+    if attention_pct < cfg.low_attention_threshold:
         stats.low_attention_minutes += watch_minutes
+        stats.low_attention_streak = (stats.low_attention_streak or 0) + 1
+    else:
+        stats.low_attention_streak = 0
 
     db.session.commit()
+    mark_history_changed(session_id)
+    trim_history(session_id)
 
     # --- Evaluate guardrails ---
-    break_needed = False
-    reason = ""
-
-    hard_limit = GUARDRAIL_CONFIG.hard_session_limit_minutes
-    low_att_limit = GUARDRAIL_CONFIG.low_attention_session_minutes
-
-    if stats.total_watch_minutes > hard_limit:
-        break_needed = True
-        reason = f"Hard session limit ({hard_limit} min) reached."
-
-    elif (
-        attention_pct < GUARDRAIL_CONFIG.low_attention_threshold
-        and stats.low_attention_minutes > low_att_limit
-    ):
-        break_needed = True
-        reason = (
-            f"Low attention ({attention_pct:.0%} completion) for over "
-            f"{low_att_limit} min."
-        )
+    break_needed, reason = evaluate_break(
+        stats.total_watch_minutes,
+        stats.low_attention_minutes,
+        attention_pct,
+        cfg,
+        daily_minutes=stats.daily_watch_minutes,
+        attention_scores=_session_attention(session_id, stats.last_reset),
+        low_streak=stats.low_attention_streak,
+        skipped=stats.skipped_watches or 0,
+        counted=stats.counted_watches,
+    )
 
     parent_override = _get_parent_override(session_id)
-    break_seconds = GUARDRAIL_CONFIG.break_length_for_hour(hour, parent_override)
-
-    if break_needed:
-        # Stats reset AFTER the break is served, not here.
-        # We just flag it so the client knows to show the break screen.
-        pass
+    break_seconds = cfg.break_length_for_hour(hour, parent_override)
 
+    outcome = _flag_break(stats, break_needed, RecordOutcome.COUNTED)
+    if outcome is RecordOutcome.BREAK_TRIGGERED:
+        _log_break(session_id, stats, attention_pct, reason, cfg)
     return {
         "recorded": True,
         "break_needed": break_needed,
         "break_seconds": break_seconds,
         "reason": reason,
+        "outcome": outcome,
+    }
+
+
+def _flag_break(
+    stats: SessionStats,
+    break_needed: bool,
+    outcome: RecordOutcome,
+) -> RecordOutcome:
+    """
+    Remember that this session has asked for a break, returning
+    BREAK_TRIGGERED the first time and `outcome` otherwise.  Stats reset
+    AFTER the break is served, not here; the client just needs to know
+    to show the break screen.
+    """
+    if break_needed and not stats.break_flagged:
+        stats.break_flagged = True
+        db.session.commit()
+        return RecordOutcome.BREAK_TRIGGERED
+    return outcome
+
+
+def _log_break(
+    session_id: str,
+    stats: SessionStats,
+    attention: float,
+    reason: str,
+    cfg: GuardrailConfig,
+) -> None:
+    """Log a newly triggered break, as DAILY_LIMIT when that is what tripped."""
+    kind = (
+        GuardrailEventKind.DAILY_LIMIT
+        if _over_daily_limit(stats.daily_watch_minutes, cfg)
+        else GuardrailEventKind.BREAK
+    )
+    _log_event(session_id, kind, stats, attention, reason)
+
+
+def events(session_id: str) -> List[GuardrailEvent]:
+    """The session's guardrail events since the last clear_events, oldest first."""
+    return list(_event_log.get(session_id, []))
+
+
+def clear_events(session_id: str) -> None:
+    """Empty the session's event log."""
+    _event_log.pop(session_id, None)
+
+
+def skip_rate(session_id: str) -> float:
+    """
+    Share of this session's reported watches that were too short to
+    count (0.0 before any report).  Read-only.
+    """
+    stats = _current_stats(session_id)
+    skipped = stats.skipped_watches or 0
+    attempts = skipped + (stats.counted_watches or 0)
+    return skipped / attempts if attempts else 0.0
+
+
+def start_break(
+    session_id: str,
+    hour: Optional[int] = None,
+    cfg: Optional[GuardrailConfig] = None,
+) -> float:
+    """
+    Begin a break and return how long is left.  The length is decided
+    here, never by the client: cfg.break_length_for_hour for `hour`
+    (default: the hour of the latest watch) or the parent override.
+
+    If a break is already running (e.g. the break screen was reloaded)
+    it is left untouched and its remaining time is returned, so a
+    reload can neither restart nor shorten it.
+    """
+    stats = _get_or_create_stats(session_id)
+    remaining = _break_remaining(stats)
+    if remaining > 0:
+        return remaining
+
+    if hour is None or not 0 <= hour <= 23:
+        hour = _local_hour(session_id)
+    cfg = (cfg or GUARDRAIL_CONFIG).for_hour(hour)
+    seconds = cfg.break_length_for_hour(hour, _get_parent_override(session_id))
+    stats.break_ends_at = datetime.utcnow() + timedelta(seconds=seconds)
+    db.session.commit()
+    return float(seconds)
+
+
+def break_state(session_id: str) -> dict:
+    """
+    Current break state:
+        {"state": "on_break", "remaining_seconds": 42.0}
+        {"state": "active",   "remaining_seconds": 0.0}
+    """
+    stats = _get_or_create_stats(session_id)
+    remaining = _break_remaining(stats)
+    return {
+        "state": "on_break" if remaining > 0 else "active",
+        "remaining_seconds": remaining,
     }
 
 
-def reset_after_break(session_id: str) -> None:
+def reset_after_break(session_id: str) -> bool:
     """
     Called when the user completes their break.
     Resets the session stats so the next session starts fresh.
+
+    Unlike the midnight reset (SessionStats.reset_daily) this keeps the
+    day's watch total, so the daily limit still applies, and deletes
+    nothing: the day's watches stay in history for the summary and the
+    feed.  Only the session minutes, the low-attention streak and the
+    window the attention trend looks at start again.
+
+    Returns False (and changes nothing) unless a break was started
+    (start_break) and has elapsed, so the session clock can't be
+    cleared without taking one.
     """
     stats = _get_or_create_stats(session_id)
+    if stats.break_ends_at is None or _break_remaining(stats) > 0:
+        return False
     stats.reset()
     db.session.commit()
+    return True
 
 
-def get_session_summary(session_id: str) -> dict:
-    """Return current stats for a session (used by the parent dashboard)."""
+def reset_session(session_id: str) -> None:
+    """
+    New session, same day (the app was closed and reopened): see
+    SessionStats.reset_session.  A running break is not affected, so
+    reopening the app never skips one.
+    """
     stats = _get_or_create_stats(session_id)
     _reset_if_new_day(stats)
-    return {
-        "total_watch_minutes": round(stats.total_watch_minutes, 1),
-        "low_attention_minutes": round(stats.low_attention_minutes, 1),
-        "last_reset": stats.last_reset.isoformat(),
-    }
+    stats.reset_session()
+    db.session.commit()
+
+
+def save_stats_json(session_id: str, path: str) -> None:
+    """
+    Write the session's guardrail counters to `path` as JSON (see
+    SessionStats.to_dict), so a limit can be carried to another install
+    along with the history from history.save_history_json.
+    """
+    stats = _get_or_create_stats(session_id)
+    with open(path, "w", encoding="utf-8") as fh:
+        json.dump(stats.to_dict(), fh, indent=2)
+
+
+def load_stats_json(session_id: str, path: str) -> bool:
+    """
+    Replace the session's counters with a file written by save_stats_json.
+    A snapshot from an earlier day keeps nothing but its date, so its
+    daily and session counters start again at zero.  Returns False, and
+    leaves the stats alone, if `path` doesn't exist.
+    """
+    try:
+        with open(path, "r", encoding="utf-8") as fh:
+            data = json.load(fh)
+    except FileNotFoundError:
+        return False
+    stats = _get_or_create_stats(session_id)
+    stats.apply_dict(data)
+    db.session.commit()
+    _reset_if_new_day(stats)
+    return True
+
+
+def consecutive_low_attention(session_id: str) -> int:
+    """Low-attention watches in a row so far this session (read-only)."""
+    return _current_stats(session_id).low_attention_streak or 0
+
+
+def daily_limit_reached(session_id: str, cfg: Optional[GuardrailConfig] = None) -> bool:
+    """True once today's watch time has hit the configured daily limit."""
+    cfg = cfg or GUARDRAIL_CONFIG
+    stats = _get_or_create_stats(session_id)
+    _reset_if_new_day(stats)
+    return _over_daily_limit(stats.daily_watch_minutes, cfg)
+
+
+def project_break_time(
+    session_id: str,
+    cfg: Optional[GuardrailConfig] = None,
+    hour: Optional[int] = None,
+) -> Optional[float]:
+    """
+    Roughly how many more seconds of watching before a break is due:
+    the time left to the hard session limit, or to the daily limit if
+    that comes first.  The attention triggers depend on how the next
+    videos are watched, so they can't be forecast and are ignored.
+
+    `hour` (default: the viewer's local hour, see _local_hour, as in
+    start_break) picks the night_mode limits when cfg has them.  None
+    once a limit has been passed.  Read-only.
+    """
+    if hour is None or not 0 <= hour <= 23:
+        hour = _local_hour(session_id)
+    cfg = (cfg or GUARDRAIL_CONFIG).for_hour(hour)
+    stats = _current_stats(session_id)
+    if stats.total_watch_minutes > cfg.hard_session_limit_minutes:
+        return None
+    if _over_daily_limit(stats.daily_watch_minutes, cfg):
+        return None
+    minutes = cfg.hard_session_limit_minutes - stats.total_watch_minutes
+    if cfg.daily_limit_minutes is not None:
+        minutes = min(minutes, cfg.daily_limit_minutes - stats.daily_watch_minutes)
+    return minutes * 60.0
+
+
+def break_suggestion(
+    hour: Optional[int] = None,
+    cfg: Optional[GuardrailConfig] = None,
+    session_id: Optional[str] = None,
+) -> str:
+    """
+    Something to do on the break, worded for the hour: bed late at
+    night, going outside in the afternoon, ... (see
+    GuardrailConfig.break_suggestions).  `hour` defaults to the
+    session's local hour (see _local_hour), the one start_break sizes
+    the break by; the current UTC hour without a session.
+    """
+    if hour is None or not 0 <= hour <= 23:
+        hour = _local_hour(session_id) if session_id else datetime.now(timezone.utc).hour
+    return (cfg or GUARDRAIL_CONFIG).suggestion_for_hour(hour)
+
+
+@dataclass
+class SessionSummary:
+    """Read-only snapshot of a session for the parent dashboard."""
+    videos_watched: int
+    total_watch_minutes: float
+    low_attention_minutes: float
+    daily_watch_minutes: float
+    attention_average: float
+    attention_weighted: float
+    break_due: bool
+    break_seconds: int
+    blocked_terms: int
+    last_reset: str
+
+    def to_dict(self) -> dict:
+        return asdict(self)
+
+    def __str__(self) -> str:
+        due = "yes" if self.break_due else "no"
+        return "\n".join([
+            f"Videos watched:   {self.videos_watched}",
+            f"Session minutes:  {self.total_watch_minutes:.1f} "
+            f"({self.daily_watch_minutes:.1f} today)",
+            f"Attention:        {self.attention_average:.0%} "
+            f"({self.attention_weighted:.0%} length-weighted)",
+            f"Break due:        {due} ({self.break_seconds // 60} min break)",
+            f"Blocked terms:    {self.blocked_terms}",
+        ])
+
+
+def _current_stats(session_id: str) -> SessionStats:
+    """
+    The session's stats as they stand now, without writing anything:
+    a blank row if none exists yet or the stored one is from a past day.
+    """
+    stats = SessionStats.query.filter_by(session_id=session_id).first()
+    last = stats.last_reset if stats is not None else None
+    if last is not None and last.tzinfo is None:
+        last = last.replace(tzinfo=timezone.utc)
+    if last is None or last.date() < datetime.now(timezone.utc).date():
+        return SessionStats(
+            session_id=session_id,
+            total_watch_minutes=0.0,
+            low_attention_minutes=0.0,
+            daily_watch_minutes=0.0,
+            last_reset=datetime.utcnow(),
+            break_ends_at=None,
+        )
+    return stats
+
+
+def get_session_summary(
+    session_id: str,
+    cfg: Optional[GuardrailConfig] = None,
+) -> SessionSummary:
+    """
+    Current state of a session (used by the parent dashboard), judged
+    at the viewer's local hour (see _local_hour).  A break already
+    flagged, e.g. by misclick skips that left no watch behind, counts
+    as due.  Nothing is reset, created or committed.
+    """
+    hour = _local_hour(session_id)
+    cfg = (cfg or GUARDRAIL_CONFIG).for_hour(hour)
+    stats = _current_stats(session_id)
+    watches = _session_watches(session_id, stats.last_reset)
+    scores = [ev.completion_ratio for ev in watches]
+
+    break_due = bool(stats.break_flagged) or _break_remaining(stats) > 0
+    if not break_due and scores:
+        break_due, _ = evaluate_break(
+            stats.total_watch_minutes,
+            stats.low_attention_minutes,
+            scores[-1],
+            cfg,
+            daily_minutes=stats.daily_watch_minutes,
+            attention_scores=scores,
+            low_streak=stats.low_attention_streak or 0,
+            skipped=stats.skipped_watches or 0,
+            counted=stats.counted_watches or 0,
+        )
+
+    return SessionSummary(
+        videos_watched=len(watches),
+        total_watch_minutes=round(stats.total_watch_minutes, 1),
+        low_attention_minutes=round(stats.low_attention_minutes, 1),
+        daily_watch_minutes=round(stats.daily_watch_minutes, 1),
+        attention_average=round(average_attention(watches, cfg), 2),
+        attention_weighted=round(weighted_attention(watches, cfg), 2),
+        break_due=break_due,
+        break_seconds=cfg.break_length_for_hour(hour, _get_parent_override(session_id)),
+        blocked_terms=len(get_blacklisted_words(session_id)),
+        last_reset=stats.last_reset.isoformat(),
+    )
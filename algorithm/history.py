@@ -1,7 +1,7 @@
 """
 Retrieves and weights watch history for the feed algorithm.
 
-Decay model (○ resolved):
+Decay model (○ resolved, DecayMode.EXPONENTIAL):
   weight = 0.5 ^ (age_in_days / half_life_days)
 
   half_life = 3 days (from FeedConfig)
@@ -12,16 +12,36 @@ Decay model (○ resolved):
 
 This strikes a balance: recent taste dominates but a coherent interest
 watched several days ago still influences the feed meaningfully.
+
+DecayMode.LINEAR is available for users who want old interests to drop
+out completely after a fixed window rather than fade asymptotically.
 """
 
 from __future__ import annotations
 
+import csv
+import json
 import math
 from datetime import datetime, timezone
-from typing import List, Dict, Any
+from typing import List, Dict, Any, Optional
+
+from database.db import db
+from database.models import WatchEvent, TagRule, ParentSettings
+from algorithm.text import normalise_tag, tokenize
+from config import FEED_CONFIG, GUARDRAIL_CONFIG, DecayMode
+
+
+# {session_id: n} bumped on every write to a session's history, so
+# anything derived from the history can tell when it has gone stale.
+_history_versions: Dict[str, int] = {}
 
-from database.models import WatchEvent
-from config import FEED_CONFIG
+
+def history_version(session_id: str) -> int:
+    return _history_versions.get(session_id, 0)
+
+
+def mark_history_changed(session_id: str) -> None:
+    _history_versions[session_id] = history_version(session_id) + 1
 
 
 def _age_days(timestamp: datetime) -> float:
@@ -34,12 +54,48 @@ def _age_days(timestamp: datetime) -> float:
     return max(delta.total_seconds() / 86400.0, 0.0)
 
 
-def _decay_weight(age_days: float) -> float:
+def _decay_weight(age_days: float, mode: DecayMode | None = None) -> float:
     """
-    Exponential decay.  weight = 0.5^(age/half_life)
+    Exponential decay (default).  weight = 0.5^(age/half_life)
     Returns a value in (0, 1].
+
+    DecayMode.LINEAR instead fades evenly to zero over
+    linear_decay_window_days:  weight = max(1 - age/window, 0)
+
+    Either is then raised to FEED_CONFIG.recency_multiplier (negative
+    values count as 0).  That steepens or flattens the curve but never
+    zeroes a watch the mode itself keeps.
     """
-    return math.pow(0.5, age_days / FEED_CONFIG.watch_half_life_days)
+    mode = mode or FEED_CONFIG.decay_mode
+    if mode == DecayMode.LINEAR:
+        window = FEED_CONFIG.linear_decay_window_days
+        weight = max(1.0 - age_days / window, 0.0)
+    else:
+        weight = math.pow(0.5, age_days / FEED_CONFIG.watch_half_life_days)
+    if weight == 0.0:
+        return 0.0
+    return math.pow(weight, max(FEED_CONFIG.recency_multiplier, 0.0))
+
+
+def _length_factor(duration_seconds: float) -> float:
+    """1 + ln(1 + minutes); 1.0 for a zero, negative or non-finite length."""
+    if not math.isfinite(duration_seconds) or duration_seconds <= 0:
+        return 1.0
+    return 1.0 + math.log1p(duration_seconds / 60.0)
+
+
+def _normalise_title(title: str) -> str:
+    """Lowercased, whitespace-collapsed title used to spot repeat watches."""
+    return " ".join(title.lower().split())
+
+
+def _rewatch_counts(events: List[WatchEvent]) -> Dict[str, int]:
+    counts: Dict[str, int] = {}
+    for ev in events:
+        key = _normalise_title(ev.video_title)
+        if key and not ev.disliked:
+            counts[key] = counts.get(key, 0) + 1
+    return counts
 
 
 def get_weighted_history(session_id: str) -> List[Dict[str, Any]]:
@@ -52,6 +108,8 @@ def get_weighted_history(session_id: str) -> List[Dict[str, Any]]:
             "video_id": str,
             "title": str,
             "hashtags": [str, ...],
+            "channel": str,
+            "language": str | None,   # ISO 639-1, if known
             "liked": bool,
             "weight": float,          # decay-adjusted importance
             "completion": float,      # 0-1 completion ratio
@@ -65,25 +123,38 @@ def get_weighted_history(session_id: str) -> List[Dict[str, Any]]:
         .all()
     )
 
+    repeats = _rewatch_counts(events)
+
     results = []
     for ev in events:
+        engagement = ev.engagement_score
+
         age = _age_days(ev.timestamp)
         base_weight = _decay_weight(age)
 
-        # Boost weight if the user liked the video
-        like_multiplier = 1.5 if ev.liked else 1.0
+        # Scale by engagement (completion + like bonus).  Barely-watched
+        # videos keep a floor so they still register as a faint signal.
+        final_weight = base_weight * max(engagement, FEED_CONFIG.engagement_floor)
+        if FEED_CONFIG.length_weighting:
+            final_weight *= _length_factor(ev.video_duration_seconds)
 
-        # Boost weight proportional to how much they watched
-        # A video watched to completion is weighted fully;
-        # one watched 10% gets 10% of the weight.
-        completion_multiplier = max(ev.completion_ratio, 0.1)
-
-        final_weight = base_weight * like_multiplier * completion_multiplier
+        # Replays are a positive signal on top of full completion,
+        # whether within one watch or as the same title watched again
+        if ev.rewatch_factor > 1.0:
+            final_weight *= min(ev.rewatch_factor, FEED_CONFIG.rewatch_boost_cap)
+        times_seen = repeats.get(_normalise_title(ev.video_title), 1)
+        if times_seen > 1:
+            final_weight *= min(
+                1.0 + FEED_CONFIG.repeat_watch_boost * (times_seen - 1),
+                FEED_CONFIG.rewatch_boost_cap,
+            )
 
         results.append({
             "video_id": ev.video_id,
             "title": ev.video_title,
             "hashtags": ev.hashtag_list,
+            "channel": ev.channel,
+            "language": ev.language,
             "liked": ev.liked,
             "weight": final_weight,
             "completion": ev.completion_ratio,
@@ -97,28 +168,631 @@ def get_disliked_signals(session_id: str) -> Dict[str, float]:
     Return a mapping of {word_or_hashtag: penalty_weight} built from
     disliked videos.  These are subtracted from or used to filter
     algorithm outputs.
+
+    Each word appears once however many disliked videos mention it;
+    repeats add to its penalty instead.  Hashtags are keyed without a
+    leading "#" so "#viral" and a title word "viral" share one entry.
+
+    With FEED_CONFIG.dislike_recovery_watches, non-disliked watches of a
+    word after its last dislike shrink its penalty (see term_penalty).
+    Manual tag rules (block_tag / allow_tag) are applied last and take
+    precedence over video dislikes.
+    """
+    return _apply_tag_rules(session_id, _video_penalties(session_id))
+
+
+def has_disliked_watches(session_id: str) -> bool:
+    """True if the session has at least one disliked watch on record."""
+    return (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == True)            # noqa: E712
+        .first()
+    ) is not None
+
+
+def get_disliked_history(session_id: str) -> List[Dict[str, Any]]:
+    """
+    The disliked watches still counted as dislikes (see expire_dislikes),
+    newest first, in the get_weighted_history shape.  The weight is the
+    temporal decay alone: engagement says nothing about a dislike.
     """
     events: List[WatchEvent] = (
         WatchEvent.query
         .filter_by(session_id=session_id)
         .filter(WatchEvent.disliked == True)            # noqa: E712
+        .order_by(WatchEvent.timestamp.desc())
         .all()
     )
+    cutoff = _dislike_cutoff(session_id)
+    return [
+        {
+            "video_id": ev.video_id,
+            "title": ev.video_title,
+            "hashtags": ev.hashtag_list,
+            "channel": ev.channel,
+            "language": ev.language,
+            "liked": ev.liked,
+            "weight": _decay_weight(_age_days(ev.timestamp)),
+            "completion": ev.completion_ratio,
+        }
+        for ev in events
+        if cutoff is None or ev.timestamp >= cutoff
+    ]
+
+
+def _video_penalties(session_id: str) -> Dict[str, float]:
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == True)            # noqa: E712
+        .all()
+    )
+    cutoff = _dislike_cutoff(session_id)
+    recovery = FEED_CONFIG.dislike_recovery_watches > 0
 
     penalties: Dict[str, float] = {}
+    last_disliked: Dict[str, datetime] = {}
     for ev in events:
-        age = _age_days(ev.timestamp)
-        weight = _decay_weight(age) * 2.0   # Dislikes penalise harder
+        if cutoff is None or ev.timestamp >= cutoff:
+            _add_penalties(penalties, ev)
+            if recovery:
+                for term, _ in _penalty_shares(ev):
+                    seen = last_disliked.get(term)
+                    last_disliked[term] = ev.timestamp if seen is None else max(seen, ev.timestamp)
+    if recovery and penalties:
+        _recover_penalties(session_id, penalties, last_disliked)
+    return penalties
+
+
+def _recover_penalties(
+    session_id: str,
+    penalties: Dict[str, float],
+    last_disliked: Dict[str, datetime],
+) -> None:
+    """
+    Win disliked words back: each non-disliked watch carrying a word
+    after its latest dislike removes 1 / dislike_recovery_watches of
+    that word's penalty, and the word is dropped once none is left.
+    """
+    needed = FEED_CONFIG.dislike_recovery_watches
+    later: Dict[str, int] = {}
+    for ev in get_active_watches(session_id):
+        for term in {t for t, _ in _penalty_shares(ev)}:
+            if term in last_disliked and ev.timestamp > last_disliked[term]:
+                later[term] = later.get(term, 0) + 1
+    for term, count in later.items():
+        remaining = 1.0 - count / needed
+        if remaining <= 0:
+            del penalties[term]
+        else:
+            penalties[term] *= remaining
 
-        # Penalise all hashtags from disliked videos
-        for tag in ev.hashtag_list:
-            tag_lower = tag.lower()
-            penalties[tag_lower] = penalties.get(tag_lower, 0.0) + weight
 
-        # Also tokenise the title
-        for word in ev.video_title.lower().split():
-            word = word.strip("\"'.,!?#@")
-            if len(word) > 2:
-                penalties[word] = penalties.get(word, 0.0) + weight * 0.5
+def _dislike_cutoff(session_id: str) -> Optional[datetime]:
+    ps: Optional[ParentSettings] = ParentSettings.query.filter_by(session_id=session_id).first()
+    return ps.dislikes_forgiven_before if ps is not None else None
 
+
+def expire_dislikes(session_id: str, before: datetime) -> None:
+    """
+    Forgive dislikes recorded before `before`: they stop adding
+    penalties, while the events themselves stay disliked (and so out of
+    the feed's history).  A word that a later dislike also carries keeps
+    that dislike's penalty.  Manual tag rules are not affected.
+
+    Each call replaces the previous cutoff.
+    """
+    ps: Optional[ParentSettings] = ParentSettings.query.filter_by(session_id=session_id).first()
+    if ps is None:
+        ps = ParentSettings(session_id=session_id)
+        db.session.add(ps)
+    ps.dislikes_forgiven_before = before
+    db.session.commit()
+    mark_history_changed(session_id)
+
+
+def _apply_tag_rules(session_id: str, penalties: Dict[str, float]) -> Dict[str, float]:
+    """
+    Allowed tags lose any penalty; blocked tags get the largest penalty
+    present (at least a fresh dislike's), so they are removed outright.
+    """
+    rules: List[TagRule] = TagRule.query.filter_by(session_id=session_id).all()
+    blocked = [r.tag for r in rules if r.blocked]
+    for rule in rules:
+        if not rule.blocked:
+            penalties.pop(rule.tag, None)
+    if blocked:
+        strongest = max([2.0, *penalties.values()])
+        for tag in blocked:
+            penalties[tag] = strongest
     return penalties
+
+
+def _set_tag_rule(session_id: str, tag: str, blocked: Optional[bool]) -> bool:
+    tag = normalise_tag(tag)
+    if not tag:
+        return False
+    rule: Optional[TagRule] = TagRule.query.filter_by(session_id=session_id, tag=tag).first()
+    if blocked is None:
+        if rule is not None:
+            db.session.delete(rule)
+    elif rule is None:
+        db.session.add(TagRule(session_id=session_id, tag=tag, blocked=blocked))
+    else:
+        rule.blocked = blocked
+    db.session.commit()
+    mark_history_changed(session_id)
+    return True
+
+
+def block_tag(session_id: str, tag: str) -> bool:
+    """
+    Suppress one hashtag without disliking any video.  Survives
+    undislike(), which only touches video-level dislikes.
+    Returns False for an empty tag.
+    """
+    return _set_tag_rule(session_id, tag, True)
+
+
+def allow_tag(session_id: str, tag: str) -> bool:
+    """
+    Keep a hashtag in the feed even if disliked videos carry it
+    (an explicit allow beats any video dislike).
+    Returns False for an empty tag.
+    """
+    return _set_tag_rule(session_id, tag, False)
+
+
+def clear_tag_rule(session_id: str, tag: str) -> bool:
+    """Remove a manual block/allow so only video dislikes apply again."""
+    return _set_tag_rule(session_id, tag, None)
+
+
+def preview_disliked_signals(session_id: str, video_id: str) -> Dict[str, float]:
+    """
+    The penalties `get_disliked_signals` would return if every watch of
+    `video_id` were disliked.  Read-only: nothing is flagged or saved.
+    """
+    penalties = _video_penalties(session_id)
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id, video_id=video_id)
+        .filter(WatchEvent.disliked == False)           # noqa: E712
+        .all()
+    )
+    for ev in events:
+        _add_penalties(penalties, ev)
+    return _apply_tag_rules(session_id, penalties)
+
+
+def _add_penalties(penalties: Dict[str, float], ev: WatchEvent) -> None:
+    """Add one disliked event's hashtag and title-word penalties."""
+    age = _age_days(ev.timestamp)
+    weight = _decay_weight(age) * 2.0   # Dislikes penalise harder
+    for term, share in _penalty_shares(ev):
+        penalties[term] = penalties.get(term, 0.0) + weight * share
+
+
+def _penalty_shares(ev: WatchEvent) -> List[tuple[str, float]]:
+    """
+    (term, share of the dislike weight) for each term a dislike of `ev`
+    penalises: every hashtag in full, and each title word at half.
+    """
+    shares = [(normalise_tag(tag), 1.0) for tag in ev.hashtag_list]
+    stop_words = FEED_CONFIG.stop_words_for(ev.language)
+    shares += [(word, 0.5) for word in tokenize(ev.video_title, stop_words, min_length=3)]
+    return shares
+
+
+def get_active_watches(session_id: str) -> List[WatchEvent]:
+    """
+    The session's non-disliked watches, oldest first, without misclicks:
+    anything shorter than GUARDRAIL_CONFIG.min_watch_seconds, the same
+    floor record_watch discards at.  Imports skip that check, so short
+    imported events exist and are filtered here.
+    """
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == False)           # noqa: E712
+        .order_by(WatchEvent.timestamp.asc())
+        .all()
+    )
+    floor = GUARDRAIL_CONFIG.min_watch_seconds
+    return [ev for ev in events if ev.watch_time_seconds >= floor]
+
+
+def get_channel_exposure(session_id: str) -> Dict[str, int]:
+    """
+    Count non-disliked watches per channel.  Events with no recorded
+    channel are left out.
+    """
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == False)           # noqa: E712
+        .all()
+    )
+    exposure: Dict[str, int] = {}
+    for ev in events:
+        if ev.channel:
+            exposure[ev.channel] = exposure.get(ev.channel, 0) + 1
+    return exposure
+
+
+def completion_rate(session_id: str, threshold: float = 0.9) -> float:
+    """
+    Share of non-disliked watches that were completed (see
+    WatchEvent.completed), 0.0 with no watches.  Unlike the attention
+    average, a video watched to 85 % counts the same as one abandoned.
+    """
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == False)           # noqa: E712
+        .all()
+    )
+    if not events:
+        return 0.0
+    return sum(ev.completed(threshold) for ev in events) / len(events)
+
+
+def get_top_hashtags(session_id: str, n: int = 10) -> List[tuple[str, int]]:
+    """
+    Raw hashtag frequency across non-disliked watches, most used first
+    (liked videos count double).  Unlike TF-IDF nothing is discounted
+    for being common; hashtags from disliked videos are left out.
+    """
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == False)           # noqa: E712
+        .all()
+    )
+    blocked = get_disliked_signals(session_id)
+    counts: Dict[str, int] = {}
+    for ev in events:
+        for tag in {normalise_tag(t) for t in ev.hashtag_list}:
+            if tag and tag not in blocked:
+                counts[tag] = counts.get(tag, 0) + (2 if ev.liked else 1)
+    ranked = sorted(counts.items(), key=lambda x: (-x[1], x[0]))
+    return ranked[:n]
+
+
+def get_hourly_distribution(session_id: str) -> List[int]:
+    """
+    Watches per hour of day (index 0-23, the viewer's local
+    hour_of_day as recorded) across the whole history, disliked videos
+    included.  Events without a valid hour are skipped.
+    """
+    events: List[WatchEvent] = WatchEvent.query.filter_by(session_id=session_id).all()
+    hours = [0] * 24
+    for ev in events:
+        if isinstance(ev.hour_of_day, int) and 0 <= ev.hour_of_day <= 23:
+            hours[ev.hour_of_day] += 1
+    return hours
+
+
+def get_rewatch_counts(session_id: str) -> Dict[str, int]:
+    """
+    {normalised_title: times_watched} over non-disliked watches, so
+    "Pasta Carbonara" and "pasta  carbonara" count as the same video.
+    """
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == False)           # noqa: E712
+        .all()
+    )
+    return _rewatch_counts(events)
+
+
+def term_penalty(session_id: str, term: str) -> float:
+    """
+    The current penalty on `term` (a word or hashtag, "#" optional), as
+    get_disliked_signals has it: after any recovery through
+    dislike_recovery_watches and tag rules.  0.0 if not disliked.
+    """
+    return get_disliked_signals(session_id).get(normalise_tag(term), 0.0)
+
+
+def get_blacklisted_words(session_id: str) -> List[str]:
+    """
+    Alphabetically sorted list of every word/hashtag currently penalised
+    by a dislike.  Stable ordering for display and for tests.
+    """
+    return sorted(get_disliked_signals(session_id))
+
+
+def undislike(session_id: str, video_id: str) -> int:
+    """
+    Reverse a dislike: clear the disliked flag on every event for
+    `video_id` in this session.  Returns the number of events changed.
+
+    Penalties are recomputed from the remaining disliked events on every
+    call to `get_disliked_signals`, so a word also contributed by another
+    disliked video stays suppressed.
+    """
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id, video_id=video_id)
+        .filter(WatchEvent.disliked == True)            # noqa: E712
+        .all()
+    )
+    for ev in events:
+        ev.disliked = False
+    db.session.commit()
+    mark_history_changed(session_id)
+    return len(events)
+
+
+def trim_history(session_id: str, max_events: Optional[int] = None) -> int:
+    """
+    Evict the session's oldest non-disliked events beyond `max_events`
+    (default FEED_CONFIG.max_history; None keeps everything) and return
+    how many went.  Disliked events are kept, as with
+    clear_history(keep_dislikes=True): penalties are rebuilt from them,
+    so evicting one would quietly lift its block.
+    """
+    cap = FEED_CONFIG.max_history if max_events is None else max_events
+    if cap is None:
+        return 0
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .filter(WatchEvent.disliked == False)           # noqa: E712
+        .order_by(WatchEvent.timestamp.desc())
+        .offset(max(cap, 0))
+        .all()
+    )
+    if not events:
+        return 0
+    for ev in events:
+        db.session.delete(ev)
+    db.session.commit()
+    mark_history_changed(session_id)
+    return len(events)
+
+
+def remove_watch(session_id: str, watched_at: datetime) -> bool:
+    """
+    Delete the single event recorded at `watched_at` (the exported
+    timestamp) and report whether one was found.
+
+    Removing a disliked watch needs no separate clean-up: penalties are
+    rebuilt from the remaining disliked events, so a word another
+    disliked video shares stays suppressed.
+    """
+    event: Optional[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id, timestamp=watched_at)
+        .first()
+    )
+    if event is None:
+        return False
+    db.session.delete(event)
+    db.session.commit()
+    mark_history_changed(session_id)
+    return True
+
+
+def clear_history(session_id: str, keep_dislikes: bool = True) -> int:
+    """
+    Delete the session's watch events and return how many were removed.
+
+    With `keep_dislikes` the disliked events are kept, so the feed starts
+    fresh but topics the child blocked stay suppressed.  Later dislikes
+    just add to them: penalties are rebuilt from the events each time.
+    """
+    query = WatchEvent.query.filter_by(session_id=session_id)
+    if keep_dislikes:
+        query = query.filter(WatchEvent.disliked == False)      # noqa: E712
+    events: List[WatchEvent] = query.all()
+    for ev in events:
+        db.session.delete(ev)
+    db.session.commit()
+    mark_history_changed(session_id)
+    return len(events)
+
+
+# ---------------------------------------------------------------------------
+# Persistence
+# ---------------------------------------------------------------------------
+
+def export_history(session_id: str) -> List[Dict[str, Any]]:
+    """
+    Return every watch event for the session (liked, disliked and neutral)
+    as plain dicts, oldest first.
+
+    Disliked events are exported like any other.  The penalty map from
+    `get_disliked_signals` is always derived from them, so there is no
+    separate blacklist to save and nothing that can drift out of sync.
+    """
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=session_id)
+        .order_by(WatchEvent.timestamp.asc())
+        .all()
+    )
+    return [ev.to_dict() for ev in events]
+
+
+def import_history(session_id: str, records: List[Dict[str, Any]]) -> int:
+    """
+    Attach previously exported events to `session_id`.
+    Returns the number of events imported.
+
+    Records that are both liked and disliked are skipped rather than
+    imported, so they can never add words to the dislike penalties.
+    So are malformed records (not a dict, or fields of the wrong type
+    or out of range, see WatchEvent.from_dict) and watch times that
+    record_watch would reject as garbage (over
+    GUARDRAIL_CONFIG.max_watch_seconds, or not finite).
+
+    Records may arrive in any order.  Recency only ever comes from each
+    event's timestamp (every query orders by it), and the events are
+    inserted oldest first so row order agrees with it too.
+    """
+    events: List[WatchEvent] = []
+    for record in records:
+        try:
+            event = WatchEvent.from_dict(session_id, record)
+        except (TypeError, ValueError, AttributeError):
+            continue
+        watch_time = event.watch_time_seconds
+        if math.isfinite(watch_time) and watch_time <= GUARDRAIL_CONFIG.max_watch_seconds:
+            events.append(event)
+    _store_events(session_id, events)
+    return len(events)
+
+
+def add_watches(session_id: str, watches: List[WatchEvent]) -> int:
+    """
+    Append many already-built events (see WatchEvent.build) to
+    `session_id` in one commit, so the feed's caches are invalidated
+    once rather than per watch.  Disliked events feed the dislike
+    penalties exactly as if recorded one by one.  No guardrail stats
+    are updated; use guardrails.record_watch for live watches.
+
+    Returns the session's history length afterwards (after any
+    FEED_CONFIG.max_history trim).
+    """
+    for ev in watches:
+        ev.session_id = session_id
+    _store_events(session_id, list(watches))
+    return WatchEvent.query.filter_by(session_id=session_id).count()
+
+
+def _store_events(session_id: str, events: List[WatchEvent]) -> None:
+    """Insert `events` oldest first, commit, and invalidate caches once."""
+    events.sort(key=lambda ev: ev.timestamp)
+    for event in events:
+        db.session.add(event)
+    db.session.commit()
+    mark_history_changed(session_id)
+    trim_history(session_id)
+
+
+def merge_sessions(target_session_id: str, source_session_id: str) -> int:
+    """
+    Fold another session's history (e.g. a second device) into
+    `target_session_id` and return how many events moved.
+
+    Every event keeps its own timestamp, so the merged history is a
+    single timeline ordered by when each video was watched, and dislikes
+    from either side count.
+    """
+    if target_session_id == source_session_id:
+        return 0
+    events: List[WatchEvent] = (
+        WatchEvent.query
+        .filter_by(session_id=source_session_id)
+        .all()
+    )
+    for ev in events:
+        ev.session_id = target_session_id
+    db.session.commit()
+    mark_history_changed(target_session_id)
+    mark_history_changed(source_session_id)
+    return len(events)
+
+
+def save_history_json(session_id: str, path: str) -> None:
+    """Write the session's history to `path` as JSON."""
+    with open(path, "w", encoding="utf-8") as fh:
+        json.dump(export_history(session_id), fh, indent=2)
+
+
+def load_history_json(session_id: str, path: str) -> int:
+    """Load a JSON file written by `save_history_json` into `session_id`."""
+    with open(path, "r", encoding="utf-8") as fh:
+        records = json.load(fh)
+    return import_history(session_id, records)
+
+
+# Column order for load_history_csv
+CSV_COLUMNS = (
+    "watch_time", "video_length", "video_name", "hashtags",
+    "liked", "disliked", "watched_at",
+)
+
+_CSV_TRUE = {"1", "true", "yes", "y"}
+_CSV_FALSE = {"0", "false", "no", "n", ""}
+
+
+def _csv_flag(value: str) -> bool:
+    value = value.strip().lower()
+    if value in _CSV_TRUE:
+        return True
+    if value in _CSV_FALSE:
+        return False
+    raise ValueError(f"not a boolean: {value!r}")
+
+
+def _csv_timestamp(value: str) -> datetime:
+    """Unix seconds or ISO 8601, as a naive UTC datetime like the ORM stores."""
+    value = value.strip()
+    try:
+        moment = datetime.fromtimestamp(float(value), timezone.utc)
+    except ValueError:
+        moment = datetime.fromisoformat(value)
+    if moment.tzinfo is not None:
+        moment = moment.astimezone(timezone.utc).replace(tzinfo=None)
+    return moment
+
+
+def _csv_record(row: List[str]) -> Dict[str, Any]:
+    """One CSV row as an export_history-style record; ValueError if malformed."""
+    if len(row) != len(CSV_COLUMNS):
+        raise ValueError(f"expected {len(CSV_COLUMNS)} columns, got {len(row)}")
+    watch_time, length, name, hashtags, liked, disliked, watched_at = row
+    watch_seconds, length_seconds = float(watch_time), float(length)
+    if not (math.isfinite(watch_seconds) and math.isfinite(length_seconds)):
+        raise ValueError("non-finite duration")
+    timestamp = _csv_timestamp(watched_at)
+    return {
+        "video_title": name.strip(),
+        "video_hashtags": "|".join(t.strip() for t in hashtags.split("|") if t.strip()),
+        "watch_time_seconds": watch_seconds,
+        "video_duration_seconds": length_seconds,
+        "liked": _csv_flag(liked),
+        "disliked": _csv_flag(disliked),
+        "hour_of_day": timestamp.hour,
+        "timestamp": timestamp.isoformat(),
+    }
+
+
+def load_history_csv(session_id: str, path: str) -> tuple[int, int]:
+    """
+    Bulk-import watches exported by another app into `session_id`.
+    Returns (imported, skipped).
+
+    The file is comma-delimited with standard CSV quoting (so a title
+    may contain commas), one watch per row, columns in this order:
+
+        watch_time,video_length,video_name,hashtags,liked,disliked,watched_at
+
+        watch_time, video_length   seconds
+        hashtags                   pipe-separated: "pasta|italian"
+        liked, disliked            true/false, yes/no or 1/0
+        watched_at                 Unix seconds or ISO 8601 (UTC if no offset)
+
+    A header row of exactly these names is optional.  Malformed rows
+    (wrong column count, unparsable values, or liked AND disliked) are
+    skipped and counted rather than aborting the import.
+    """
+    records: List[Dict[str, Any]] = []
+    skipped = 0
+    with open(path, "r", encoding="utf-8", newline="") as fh:
+        for i, row in enumerate(csv.reader(fh)):
+            if not row:
+                continue
+            if i == 0 and tuple(c.strip().lower() for c in row) == CSV_COLUMNS:
+                continue
+            try:
+                records.append(_csv_record(row))
+            except ValueError:
+                skipped += 1
+    imported = import_history(session_id, records)
+    return imported, skipped + len(records) - imported
@@ -7,48 +7,116 @@ Produces candidate next-words given a seed context.
 
 from __future__ import annotations
 
+import bisect
+import itertools
+import math
 import random
-import re
 from collections import defaultdict
-from typing import Dict, List, Tuple
+from typing import Dict, FrozenSet, Iterable, List, Mapping, Optional, Sequence, Tuple
 
+from algorithm.text import section_tokens
 from config import FEED_CONFIG
 
 
-def _tokenize(text: str) -> List[str]:
-    """Lowercase, strip punctuation, split into words."""
-    text = text.lower()
-    text = re.sub(r"[^a-z0-9\s]", " ", text)
-    return [w for w in text.split() if len(w) > 1]
-
-
 class MarkovChain:
     """
     Variable-order Markov chain.
     order=2 means we use bigram (2-word) contexts.
 
+    Lower-order tables (context lengths 1 .. order-1) are trained
+    alongside the full-order table so that `generate` can genuinely
+    back off when the full context has never been seen.  With order=1
+    there are no back-off tables and behaviour is a plain first-order
+    chain.
+
     The chain is weighted: adding a document with weight W
     increments transition counts by W rather than 1.
     This naturally makes recent / liked videos dominate.
+
+    All sampling goes through `rng`, an instance of `random.Random`.
+    By default it is seeded from system entropy so the feed varies
+    between visits; pass a seeded instance (or use `with_seed`) for
+    reproducible walks in tests.
+
+    Stop words are removed before training so that filler such as
+    "how to" never becomes a transition.  `stop_words` defaults to
+    FEED_CONFIG.stop_words.
+
+    A transition into a title word is further scaled by `title_weight`
+    and one into a hashtag (after the "|") by `hashtag_weight`; both
+    default to the FEED_CONFIG values.
+
+    `avoid` may hold a second chain, trained the same way on text the
+    viewer rejected.  A step from word w to candidate c is then skipped
+    whenever the avoid chain's first-order weight for w -> c is at least
+    this chain's, so a pairing seen mostly in disliked titles never
+    comes up in a walk, in `next_words` or in `top_transitions`.
     """
 
-    def __init__(self, order: int = FEED_CONFIG.markov_order):
+    def __init__(
+        self,
+        order: int = FEED_CONFIG.markov_order,
+        rng: random.Random | None = None,
+        stop_words: Optional[Iterable[str]] = None,
+        title_weight: Optional[float] = None,
+        hashtag_weight: Optional[float] = None,
+    ):
         self.order = order
+        self.title_weight = FEED_CONFIG.title_weight if title_weight is None else title_weight
+        self.hashtag_weight = (
+            FEED_CONFIG.hashtag_weight if hashtag_weight is None else hashtag_weight
+        )
+        self._rng = rng if rng is not None else random.Random()
+        self.stop_words: FrozenSet[str] = (
+            FEED_CONFIG.stop_words if stop_words is None else frozenset(stop_words)
+        )
         # {context_tuple: {next_word: accumulated_weight}}
         self._transitions: Dict[Tuple, Dict[str, float]] = defaultdict(
             lambda: defaultdict(float)
         )
+        # {context_len: {context_tuple: {next_word: accumulated_weight}}}
+        # for every context_len < order; only consulted when backing off.
+        self._backoff: Dict[int, Dict[Tuple, Dict[str, float]]] = {}
         self._start_tokens: List[str] = []
+        self.avoid: Optional[MarkovChain] = None
+
+    @classmethod
+    def with_seed(
+        cls,
+        seed: int,
+        order: int = FEED_CONFIG.markov_order,
+        stop_words: Optional[Iterable[str]] = None,
+    ) -> "MarkovChain":
+        """Build a chain whose random walk is deterministic for `seed`."""
+        return cls(order=order, rng=random.Random(seed), stop_words=stop_words)
 
     def train(self, documents: List[Tuple[str, float]]) -> None:
         """
         documents: list of (text, weight) pairs.
         """
         self._transitions.clear()
+        self._backoff = {
+            ctx_len: defaultdict(lambda: defaultdict(float))
+            for ctx_len in range(1, self.order)
+        }
         self._start_tokens.clear()
 
         for text, weight in documents:
-            tokens = _tokenize(text)
+            sections = section_tokens(text, self.stop_words, min_length=2)
+            tokens = [w for w, _ in sections]
+            # Weight of a transition *into* each position
+            step_weights = [
+                weight * (self.hashtag_weight if is_tag else self.title_weight)
+                for _, is_tag in sections
+            ]
+
+            # Back-off tables also learn from documents too short for
+            # the full order.
+            for ctx_len, table in self._backoff.items():
+                for i in range(len(tokens) - ctx_len):
+                    context = tuple(tokens[i : i + ctx_len])
+                    table[context][tokens[i + ctx_len]] += step_weights[i + ctx_len]
+
             if len(tokens) < self.order + 1:
                 continue
 
@@ -60,59 +128,188 @@ class MarkovChain:
             for i in range(len(tokens) - self.order):
                 context = tuple(tokens[i : i + self.order])
                 next_word = tokens[i + self.order]
-                self._transitions[context][next_word] += weight
+                self._transitions[context][next_word] += step_weights[i + self.order]
 
-    def generate(self, length: int = FEED_CONFIG.num_output_words) -> List[str]:
+    def generate(
+        self,
+        length: int = FEED_CONFIG.num_output_words,
+        restart_seeds: Optional[Sequence[str]] = None,
+        seed_scores: Optional[Mapping[str, float]] = None,
+    ) -> List[str]:
         """
         Generate `length` words by random walk through the chain.
         Falls back to shorter contexts if the current context is unseen.
+
+        `seed_scores` (e.g. the top TF-IDF words with their scores) picks
+        the start word with probability proportional to its score, so a
+        strong second interest sometimes leads instead of always the top
+        one.  Seeds the chain has never seen, and non-positive scores,
+        are ignored; with none left the start is drawn from title
+        openings as usual.
+
+        By default a context unseen at every order falls back to any
+        known word, weighted by frequency.  With `restart_seeds` (e.g.
+        the top TF-IDF words) the walk instead restarts from a random
+        unused seed at such a dead end and collects `length` distinct
+        words, stopping early once the seeds run out or after
+        10 * length steps.  A walk caught in a loop that adds no new
+        words is treated as a dead end too.  `seed_scores` then weights
+        the first start; later restarts draw uniformly.
         """
         if not self._transitions:
             return []
+        seeds = self._usable_seeds(seed_scores or {})
+        if restart_seeds is not None:
+            return self._walk_with_restarts(length, restart_seeds, seeds)
 
+        if seeds:
+            current = [self._weighted_choice(seeds)]
         # Pick a starting word weighted by frequency
-        if self._start_tokens:
-            current = [random.choice(self._start_tokens)]
+        elif self._start_tokens:
+            current = [self._rng.choice(self._start_tokens)]
         else:
-            current = [random.choice(list(self._transitions.keys()))[0]]
+            current = [self._rng.choice(list(self._transitions.keys()))[0]]
 
         result = list(current)
 
         for _ in range(length - 1):
-            # Try longest context first, then back off
-            generated = False
-            for ctx_len in range(self.order, 0, -1):
-                context = tuple(result[-ctx_len:])
-                if context in self._transitions:
-                    candidates = self._transitions[context]
-                    words = list(candidates.keys())
-                    weights = list(candidates.values())
-                    chosen = random.choices(words, weights=weights, k=1)[0]
-                    result.append(chosen)
-                    generated = True
+            next_word = self._next_word(result)
+            if next_word is None:
+                # Total fallback: pick any known next word, still weighted
+                # by how often it was seen across the whole chain.
+                totals = self._unavoided(result[-1], self._word_totals())
+                if not totals:
                     break
+                next_word = self._weighted_choice(totals)
+            result.append(next_word)
+
+        return result
+
+    def _usable_seeds(self, seed_scores: Mapping[str, float]) -> Dict[str, float]:
+        """Lowercased `seed_scores` with a finite positive score and known to the chain."""
+        known = self._word_totals()
+        return {
+            w.lower(): s for w, s in seed_scores.items()
+            if math.isfinite(s) and s > 0
+            and (w.lower() in known or (w.lower(),) in self._table(1))
+        }
+
+    def _next_word(self, walk: List[str]) -> Optional[str]:
+        """Sample a follower of the walk so far, backing off; None at a dead end."""
+        # Try longest context first, then back off
+        for ctx_len in range(self.order, 0, -1):
+            context = tuple(walk[-ctx_len:])
+            if len(context) < ctx_len:
+                continue
+            table = self._table(ctx_len)
+            if context in table:
+                followers = self._unavoided(walk[-1], table[context])
+                if followers:
+                    return self._weighted_choice(followers)
+        return None
 
-            if not generated:
-                # Total fallback: pick any known next word
-                all_nexts = [
-                    w for nexts in self._transitions.values() for w in nexts
-                ]
-                if all_nexts:
-                    result.append(random.choice(all_nexts))
-                else:
+    def _unavoided(self, word: str, followers: Dict[str, float]) -> Dict[str, float]:
+        """`followers` of `word` without those the avoid chain claims (see class doc)."""
+        if self.avoid is None:
+            return followers
+        shunned = self.avoid._table(1).get((word,), {})
+        own = self._table(1).get((word,), {})
+        return {
+            w: weight for w, weight in followers.items()
+            if shunned.get(w, 0.0) <= 0 or shunned[w] < own.get(w, 0.0)
+        }
+
+    def _walk_with_restarts(
+        self,
+        length: int,
+        seeds: Sequence[str],
+        first: Optional[Dict[str, float]] = None,
+    ) -> List[str]:
+        pool = list(dict.fromkeys(s.lower() for s in seeds))
+        if first:
+            start = self._weighted_choice(first)
+            pool = [start] + [s for s in pool if s != start]
+        walk: List[str] = []
+        result: List[str] = []
+        stalled = 0                         # Steps since the last new word
+
+        for _ in range(length * 10):       # Guard against cycling forever
+            if len(result) >= length:
+                break
+            next_word = self._next_word(walk) if walk else None
+            if next_word is None:
+                if not pool:
                     break
+                index = 0 if first and not result else self._rng.randrange(len(pool))
+                next_word = pool.pop(index)
+                walk = []
+            walk.append(next_word)
+            if next_word not in result:
+                result.append(next_word)
+                stalled = 0
+                continue
+            # Going round a loop (a -> b -> a ...): once the walk has
+            # stalled for longer than the word has followers, treat it
+            # as a dead end and restart from the next seed.
+            stalled += 1
+            if stalled > len(self._table(1).get((next_word,), {})):
+                walk = []
+                stalled = 0
 
         return result
 
+    def next_words(self, word: str) -> List[str]:
+        """
+        Distinct words seen directly after `word`, most heavily weighted
+        first (ties alphabetical).  Empty if `word` was never seen.
+        Followers claimed by the avoid chain are left out.
+        """
+        followers = self._unavoided(word.lower(), self._table(1).get((word.lower(),), {}))
+        ranked = sorted(followers.items(), key=lambda x: (-x[1], x[0]))
+        return [w for w, _ in ranked]
+
+    def _table(self, ctx_len: int) -> Dict[Tuple, Dict[str, float]]:
+        """Transition table for a given context length."""
+        if ctx_len == self.order:
+            return self._transitions
+        return self._backoff.get(ctx_len, {})
+
+    def _weighted_choice(self, candidates: Dict[str, float]) -> str:
+        """
+        Sample one word with probability proportional to its accumulated
+        transition weight.
+
+        A cumulative weight table is built and searched with a single
+        uniform draw.  When every candidate carries the same weight
+        (e.g. each transition was seen exactly once in equally-weighted
+        documents) this degrades to a uniform choice.  If all weights
+        are zero the choice is uniform as well.
+        """
+        words = list(candidates.keys())
+        cumulative = list(itertools.accumulate(candidates.values()))
+        total = cumulative[-1]
+        if total <= 0:
+            return self._rng.choice(words)
+        draw = self._rng.random() * total
+        index = bisect.bisect_right(cumulative, draw)
+        return words[min(index, len(words) - 1)]
+
+    def _word_totals(self) -> Dict[str, float]:
+        """
+        Total incoming transition weight per word across all contexts,
+        leaving out transitions the avoid chain claims.
+        """
+        totals: Dict[str, float] = defaultdict(float)
+        for context, nexts in self._transitions.items():
+            for word, weight in self._unavoided(context[-1], nexts).items():
+                totals[word] += weight
+        return totals
+
     def top_transitions(self, top_n: int = 20) -> List[Tuple[str, float]]:
         """
         Return the (word, total_weight) pairs across all transitions,
         sorted by total weight descending.  Used by the hybrid model
         to extract strong signals.
         """
-        totals: Dict[str, float] = defaultdict(float)
-        for nexts in self._transitions.values():
-            for word, weight in nexts.items():
-                totals[word] += weight
-
+        totals = self._word_totals()
         return sorted(totals.items(), key=lambda x: x[1], reverse=True)[:top_n]
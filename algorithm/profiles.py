@@ -0,0 +1,60 @@
+"""
+Separate recommendation profiles on one shared device.
+
+Everything in the engine is keyed by session id, so a profile is just
+its own session id derived from the device's: "<device>:<user>".  Each
+profile therefore has its own history, dislikes, tag rules and
+guardrail stats, and a parent's viewing never leaks into a child's feed.
+
+Profiles are created lazily the first time a user id is used.  The
+registry is in-process, like the feed's keyword cache; the data behind
+each profile lives in the database under its session id and survives
+a restart.
+"""
+
+from __future__ import annotations
+
+from typing import Any, Dict, List, Optional
+
+from algorithm.feed import feed_keywords
+from algorithm.guardrails import record_watch
+from config import FEED_CONFIG
+
+
+class Profiles:
+    """The users of one device and which of them is watching."""
+
+    def __init__(self, device_id: str):
+        self.device_id = device_id
+        self._sessions: Dict[str, str] = {}      # {user_id: session_id}
+        self.active: Optional[str] = None
+
+    def session_for(self, user_id: str) -> str:
+        """Session id of `user_id`'s profile, creating the profile if new."""
+        if user_id not in self._sessions:
+            self._sessions[user_id] = f"{self.device_id}:{user_id}"
+        return self._sessions[user_id]
+
+    def switch(self, user_id: str) -> str:
+        """Make `user_id` the active profile; returns its session id."""
+        self.active = user_id
+        return self.session_for(user_id)
+
+    def list(self) -> List[str]:
+        """Known user ids, in the order they were first used."""
+        return list(self._sessions)
+
+    def add_watch(self, user_id: str, **watch: Any) -> dict:
+        """
+        Record a watch for `user_id` only.  Keyword arguments are those
+        of guardrails.record_watch after the session id.
+        """
+        return record_watch(self.session_for(user_id), **watch)
+
+    def generate_query(
+        self,
+        user_id: str,
+        n: int = FEED_CONFIG.num_output_words,
+    ) -> List[str]:
+        """Feed keywords from `user_id`'s own history (see feed.feed_keywords)."""
+        return feed_keywords(self.session_for(user_id), n)
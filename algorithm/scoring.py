@@ -0,0 +1,163 @@
+"""
+Pluggable term ranking for the hybrid recommendation engine.
+
+The TF-IDF side of the hybrid asks a `TermScorer` which words best
+describe the watch history.  TfIdfScorer is the default; anything with
+a matching `score_terms` method can be dropped in with
+feed.set_term_scorer to try another scheme without forking the feed.
+
+Bm25Scorer is the built-in alternative.  It scores the same documents
+and terms as TF-IDF, but saturates repeated words (k1) and discounts
+terms from long documents (b), which suits titles that range from two
+words to twenty.
+"""
+
+from __future__ import annotations
+
+import math
+from collections import defaultdict
+from typing import AbstractSet, Callable, Dict, List, Optional, Protocol, Tuple
+
+from algorithm.text import stem
+from algorithm.tfidf import TFIDF
+from config import FEED_CONFIG
+
+
+class TermScorer(Protocol):
+    """Ranks the terms of a weighted corpus, best first."""
+
+    def score_terms(
+        self,
+        documents: List[Tuple[str, float]],
+        blacklist: AbstractSet[str] = frozenset(),
+    ) -> List[Tuple[str, float]]:
+        """
+        (term, score) pairs for every term in `documents` ((text,
+        weight) pairs as built by the feed), highest score first.
+        Terms in `blacklist`, and phrases containing one of its words,
+        are left out.
+        """
+        ...
+
+
+def _pick(value: Optional[bool], default: bool) -> bool:
+    return default if value is None else value
+
+
+def _unblocked(
+    ranked: List[Tuple[str, float]],
+    blacklist: AbstractSet[str],
+    stemming: bool,
+) -> List[Tuple[str, float]]:
+    """`ranked` without blacklisted words or phrases, matched on stems if stemming."""
+    key: Callable[[str], str] = stem if stemming else (lambda w: w)
+    blocked = {key(w) for w in blacklist}
+    return [
+        (term, score) for term, score in ranked
+        if not any(key(part) in blocked for part in term.split())
+    ]
+
+
+class TfIdfScorer:
+    """
+    TermScorer backed by TFIDF.top_words.  Options left as None follow
+    the FEED_CONFIG values at scoring time (tfidf_include_bigrams,
+    stemming, tfidf_smooth_idf), so patching the config still applies;
+    so does FEED_CONFIG.tfidf_workers.
+    """
+
+    def __init__(
+        self,
+        include_bigrams: Optional[bool] = None,
+        stemming: Optional[bool] = None,
+        smooth_idf: Optional[bool] = None,
+    ):
+        self.include_bigrams = include_bigrams
+        self.stemming = stemming
+        self.smooth_idf = smooth_idf
+
+    def _model(self) -> TFIDF:
+        return TFIDF(
+            include_bigrams=_pick(self.include_bigrams, FEED_CONFIG.tfidf_include_bigrams),
+            stemming=_pick(self.stemming, FEED_CONFIG.stemming),
+            smooth_idf=_pick(self.smooth_idf, FEED_CONFIG.tfidf_smooth_idf),
+            workers=FEED_CONFIG.tfidf_workers,
+        )
+
+    def score_terms(
+        self,
+        documents: List[Tuple[str, float]],
+        blacklist: AbstractSet[str] = frozenset(),
+    ) -> List[Tuple[str, float]]:
+        tfidf = self._model()
+        tfidf.fit(documents)
+        ranked = tfidf.top_words(documents, top_n=None)
+        return _unblocked(ranked, blacklist, tfidf.stemming)
+
+
+class Bm25Scorer:
+    """
+    Okapi BM25 summed over the corpus: each document adds
+
+        weight * idf(t) * tf * (k1 + 1) / (tf + k1 * (1 - b + b * len / avg_len))
+
+    for each of its terms, where idf(t) = ln(1 + (N - df + 0.5) / (df + 0.5)).
+    Terms, title/hashtag weighting and stemming come from TFIDF, and N
+    and df are weighted by document weight just as TFIDF.fit does, so
+    the two scorers differ only in the formula.  `include_bigrams` and
+    `stemming` left as None follow FEED_CONFIG.
+    """
+
+    def __init__(
+        self,
+        k1: float = 1.5,
+        b: float = 0.75,
+        include_bigrams: Optional[bool] = None,
+        stemming: Optional[bool] = None,
+    ):
+        if k1 < 0 or not 0.0 <= b <= 1.0:
+            raise ValueError(f"need k1 >= 0 and 0 <= b <= 1, got k1={k1}, b={b}")
+        self.k1 = k1
+        self.b = b
+        self.include_bigrams = include_bigrams
+        self.stemming = stemming
+
+    def score_terms(
+        self,
+        documents: List[Tuple[str, float]],
+        blacklist: AbstractSet[str] = frozenset(),
+    ) -> List[Tuple[str, float]]:
+        terms = TFIDF(
+            include_bigrams=_pick(self.include_bigrams, FEED_CONFIG.tfidf_include_bigrams),
+            stemming=_pick(self.stemming, FEED_CONFIG.stemming),
+        )
+        docs = []
+        for text, weight in documents:
+            if not math.isfinite(weight):
+                continue
+            counts = terms._weighted_counts(text)
+            if counts:
+                docs.append((counts, sum(counts.values()), weight))
+        if not docs:
+            return []
+
+        df: Dict[str, float] = defaultdict(float)
+        for counts, _, weight in docs:
+            for term in counts:
+                df[term] += weight
+        corpus_size = max(sum(weight for _, _, weight in docs), 1.0)
+        avg_len = sum(length for _, length, _ in docs) / len(docs)
+
+        scores: Dict[str, float] = defaultdict(float)
+        for counts, length, weight in docs:
+            relative_len = length / avg_len if avg_len > 0 else 1.0
+            norm = self.k1 * (1.0 - self.b + self.b * relative_len)
+            for term, tf in counts.items():
+                idf = math.log(1.0 + (corpus_size - df[term] + 0.5) / (df[term] + 0.5))
+                scores[term] += weight * idf * tf * (self.k1 + 1.0) / (tf + norm)
+
+        if terms.stemming:
+            scores = {terms.surface(term): score for term, score in scores.items()}
+        finite = [(t, s) for t, s in scores.items() if math.isfinite(s)]
+        ranked = sorted(finite, key=lambda x: (-x[1], x[0]))
+        return _unblocked(ranked, blacklist, terms.stemming)
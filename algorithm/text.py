@@ -0,0 +1,180 @@
+"""
+Shared text helpers for the recommendation models.
+
+The stemmer is a deliberately light take on Porter's step 1: it only
+collapses plural and -ed / -ing forms, which is where nearly all of the
+splitting happens in video titles ("recipe" / "recipes",
+"cook" / "cooking").  Stems are used for matching only; callers keep
+the original surface form around for display.
+
+`tokenize` is the one word splitter every model uses, so a title word
+and a hashtag always normalise the same way.  `set_tokenizer` swaps in
+custom preprocessing (emoji stripping, transliteration, ...) for all of
+them at once.
+"""
+
+from __future__ import annotations
+
+import re
+from typing import Callable, FrozenSet, List, Optional, Tuple
+
+_VOWELS = set("aeiou")
+
+# Runs of letters/digits, optionally joined by an apostrophe or hyphen
+_TOKEN_RE = re.compile(r"[^\W_]+(?:['-][^\W_]+)*")
+
+# Replacement word splitter installed by set_tokenizer; None = _TOKEN_RE
+_custom_tokenizer: Optional[Callable[[str], List[str]]] = None
+
+
+def set_tokenizer(splitter: Optional[Callable[[str], List[str]]]) -> None:
+    """
+    Split text with `splitter` instead of the built-in rule everywhere
+    `tokenize` and `normalise_tag` are used; None restores the default.
+
+    `splitter` gets the raw text and must return the words to keep,
+    already lowercased and without punctuation ("|" included, which
+    separates a corpus document's title from its hashtags).  Stop
+    words and `min_length` are still applied afterwards.  Keyword and
+    chain caches built with the old splitter are not dropped here; see
+    feed.clear_keyword_cache.
+    """
+    global _custom_tokenizer
+    _custom_tokenizer = splitter
+
+
+def tokenize(
+    text: str,
+    stop_words: FrozenSet[str] = frozenset(),
+    min_length: int = 1,
+) -> List[str]:
+    """
+    Lowercase `text` and split it into words, dropping surrounding
+    punctuation and stop words:
+
+        "Grandma's Pasta, Step-by-Step!" -> ["grandma's", "pasta", "step-by-step"]
+
+    "|" and any other punctuation between words act as separators.
+    Tokens shorter than `min_length` are dropped.
+    """
+    if _custom_tokenizer is not None:
+        words = _custom_tokenizer(text)
+    else:
+        words = _TOKEN_RE.findall(text.lower().replace("\u2019", "'"))
+    return [w for w in words if len(w) >= min_length and w not in stop_words]
+
+
+def normalise_tag(tag: str) -> str:
+    """
+    Canonical form of a hashtag for dislikes and tag rules: "#Pasta " ->
+    "pasta".  With a custom tokenizer its words are joined by spaces.
+    """
+    if _custom_tokenizer is not None:
+        return " ".join(_custom_tokenizer(tag.strip().lstrip("#")))
+    return tag.strip().lower().lstrip("#")
+
+
+def section_tokens(
+    text: str,
+    stop_words: FrozenSet[str] = frozenset(),
+    min_length: int = 1,
+) -> List[Tuple[str, bool]]:
+    """
+    Tokenize a corpus document ("title | tags ...") as `tokenize` does,
+    pairing each token with whether it sits in the hashtag section
+    (anything after the first "|").
+    """
+    title, _, tags = text.partition("|")
+    return (
+        [(w, False) for w in tokenize(title, stop_words, min_length)]
+        + [(w, True) for w in tokenize(tags, stop_words, min_length)]
+    )
+
+
+def edit_distance(a: str, b: str, limit: Optional[int] = None) -> int:
+    """
+    Levenshtein distance between `a` and `b` (insertions, deletions and
+    substitutions).  With `limit`, gives up early and returns limit + 1
+    once the distance is known to be larger.
+    """
+    if limit is not None and abs(len(a) - len(b)) > limit:
+        return limit + 1
+    previous = list(range(len(b) + 1))
+    for i, ca in enumerate(a, start=1):
+        current = [i]
+        for j, cb in enumerate(b, start=1):
+            current.append(min(
+                previous[j] + 1,                # Delete from a
+                current[j - 1] + 1,             # Insert into a
+                previous[j - 1] + (ca != cb),   # Substitute
+            ))
+        if limit is not None and min(current) > limit:
+            return limit + 1
+        previous = current
+    return previous[-1]
+
+
+def _is_cvc(word: str) -> bool:
+    """Ends consonant-vowel-consonant, last letter not w/x/y (e.g. "bak")."""
+    if len(word) < 3:
+        return False
+    c1, v, c2 = word[-3], word[-2], word[-1]
+    return (
+        c1 not in _VOWELS
+        and v in _VOWELS
+        and c2 not in _VOWELS
+        and c2 not in "wxy"
+    )
+
+
+def _measure(word: str) -> int:
+    """Porter's m: number of vowel-consonant runs ("bak" = 1, "open" = 2)."""
+    pattern = "".join("v" if ch in _VOWELS else "c" for ch in word)
+    return re.sub(r"v+", "v", pattern).count("vc")
+
+
+def stem(word: str) -> str:
+    """
+    Light suffix stripping:
+
+        recipes -> recipe     stories -> story     glass -> glass
+        dishes  -> dish       boxes   -> box
+        cooking -> cook       baking  -> bake      running -> run
+        cooked  -> cook
+
+    Words of three letters or fewer are returned unchanged, and a
+    possessive "'s" is dropped first (grandma's -> grandma).
+    """
+    if word.endswith("'s"):
+        word = word[:-2]
+    if len(word) <= 3:
+        return word
+
+    # Plurals
+    if word.endswith("sses"):
+        return word[:-2]
+    if word.endswith("ies"):
+        return word[:-3] + "y"
+    if word.endswith(("ches", "shes", "xes")):
+        return word[:-2]
+    if word.endswith("s") and not word.endswith(("ss", "us", "is")):
+        return word[:-1]
+
+    # -ing / -ed
+    if word.endswith("eed"):
+        return word
+    for suffix in ("ing", "ed"):
+        if not word.endswith(suffix):
+            continue
+        base = word[: -len(suffix)]
+        if len(base) < 3 or not any(ch in _VOWELS for ch in base):
+            return word
+        # Undouble: "runn" -> "run" (but keep "ll", "ss", "zz")
+        if base[-1] == base[-2] and base[-1] not in "lsz":
+            return base[:-1]
+        # Restore a silent e on short stems: "bak" -> "bake"
+        if _measure(base) == 1 and _is_cvc(base):
+            return base + "e"
+        return base
+
+    return word
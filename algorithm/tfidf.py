@@ -10,31 +10,24 @@ words across the whole corpus (high IDF = rare globally = distinctive).
 from __future__ import annotations
 
 import math
-import re
 from collections import defaultdict, Counter
-from typing import Dict, List, Tuple
+from concurrent.futures import ProcessPoolExecutor
+from typing import Dict, FrozenSet, Iterable, List, Optional, Tuple
 
+from algorithm.text import stem, tokenize
+from config import FEED_CONFIG
 
-# A small English stopword list.  We intentionally keep it tight so that
-# meaningful short words (e.g. "art", "war", "how") are not removed.
-STOPWORDS = {
-    "the", "a", "an", "and", "or", "but", "in", "on", "at", "to",
-    "for", "of", "with", "is", "it", "this", "that", "was", "are",
-    "be", "as", "by", "from", "have", "has", "had", "not", "they",
-    "he", "she", "we", "you", "i", "my", "your", "his", "her", "its",
-    "do", "did", "will", "would", "could", "should", "may", "might",
-    "can", "been", "being", "their", "our", "all", "more", "so", "if",
-    "than", "then", "there", "when", "where", "which", "who", "what",
-    "how", "just", "up", "out", "about", "into", "through", "also",
-    "very", "much", "many", "some", "any", "one", "two", "new", "get",
-    "no", "yes", "hi", "me",
-}
 
-
-def _tokenize(text: str) -> List[str]:
-    text = text.lower()
-    text = re.sub(r"[^a-z0-9\s]", " ", text)
-    return [w for w in text.split() if len(w) > 2 and w not in STOPWORDS]
+def cosine_similarity(a: Dict[str, float], b: Dict[str, float]) -> float:
+    """Cosine similarity of two sparse {term: score} vectors (0 if either is empty)."""
+    if not a or not b:
+        return 0.0
+    dot = sum(score * b[term] for term, score in a.items() if term in b)
+    norm_a = math.sqrt(sum(v * v for v in a.values()))
+    norm_b = math.sqrt(sum(v * v for v in b.values()))
+    if norm_a == 0 or norm_b == 0:
+        return 0.0
+    return dot / (norm_a * norm_b)
 
 
 class TFIDF:
@@ -43,11 +36,109 @@ class TFIDF:
     document frequencies natively.
 
     Each 'document' is a (text, weight) pair.
+
+    `stop_words` defaults to FEED_CONFIG.stop_words; pass any iterable
+    of lowercase words to override it.
+
+    With `include_bigrams=True` every pair of adjacent (non-stop) words
+    is also scored as a single term, e.g. "pasta carbonara".  Pairs are
+    never formed across a "|" so the title and hashtag sections of a
+    corpus document stay separate.
+
+    With `stemming=True` terms are counted by stem, so "recipe" and
+    "recipes" share one score.  `top_words` still reports the most
+    common surface form ("recipe", never "recip").
+
+    Term frequency counts each occurrence in a document's title section
+    as `title_weight` and each one after the first "|" (the hashtags)
+    as `hashtag_weight`; both default to the FEED_CONFIG values.  IDF
+    is unaffected, since it only asks which documents contain a term.
+
+    IDF is smoothed by default, ln((1 + N) / (1 + df)) + 1, so a word
+    found in every document keeps a small positive weight.  With
+    `smooth_idf=False` the classic ln(N / df) is used instead, which
+    zeroes such words entirely.
+
+    With `workers` > 1, `top_words` scores the documents in that many
+    worker processes and merges their partial sums, for histories of
+    thousands of watches (below that, starting the pool costs more than
+    it saves).  Terms and ranking are those of the serial path; scores
+    are summed in a different order, so they agree to within float
+    rounding (relative error around 1e-12), and a tie decided by that
+    rounding can swap two adjacent terms.  A tokenizer installed with
+    text.set_tokenizer only reaches the workers on platforms that fork.
     """
 
-    def __init__(self):
+    def __init__(
+        self,
+        stop_words: Optional[Iterable[str]] = None,
+        include_bigrams: bool = False,
+        stemming: bool = False,
+        title_weight: Optional[float] = None,
+        hashtag_weight: Optional[float] = None,
+        smooth_idf: bool = True,
+        workers: int = 1,
+    ):
+        self.workers = max(1, workers)
         self._idf: Dict[str, float] = {}
         self._corpus_size: int = 0
+        self.stop_words: FrozenSet[str] = (
+            FEED_CONFIG.stop_words if stop_words is None else frozenset(stop_words)
+        )
+        self.include_bigrams = include_bigrams
+        self.stemming = stemming
+        self.smooth_idf = smooth_idf
+        self.title_weight = FEED_CONFIG.title_weight if title_weight is None else title_weight
+        self.hashtag_weight = (
+            FEED_CONFIG.hashtag_weight if hashtag_weight is None else hashtag_weight
+        )
+        # {stem: Counter(surface_form)}, filled while stemming
+        self._surface: Dict[str, Counter] = defaultdict(Counter)
+
+    def _tokens(self, text: str) -> List[str]:
+        tokens = tokenize(text, self.stop_words, min_length=3)
+        if not self.stemming:
+            return tokens
+        stems = []
+        for token in tokens:
+            root = stem(token)
+            self._surface[root][token] += 1
+            stems.append(root)
+        return stems
+
+    def surface(self, term: str) -> str:
+        """Most common original spelling of a (possibly stemmed) term."""
+        if not self.stemming:
+            return term
+        parts = []
+        for root in term.split():
+            forms = self._surface.get(root)
+            if forms:
+                parts.append(min(forms.items(), key=lambda x: (-x[1], x[0]))[0])
+            else:
+                parts.append(root)
+        return " ".join(parts)
+
+    def _segment_terms(self, segment: str) -> List[str]:
+        tokens = self._tokens(segment)
+        if not self.include_bigrams:
+            return tokens
+        return tokens + [f"{a} {b}" for a, b in zip(tokens, tokens[1:])]
+
+    def _terms(self, text: str) -> List[str]:
+        """Unigrams, plus adjacent bigrams when enabled."""
+        if not self.include_bigrams:
+            return self._tokens(text)
+        return [t for segment in text.split("|") for t in self._segment_terms(segment)]
+
+    def _weighted_counts(self, text: str) -> Dict[str, float]:
+        """{term: occurrences}, title and hashtag sections weighted separately."""
+        counts: Dict[str, float] = defaultdict(float)
+        for i, segment in enumerate(text.split("|")):
+            section_weight = self.hashtag_weight if i else self.title_weight
+            for term in self._segment_terms(segment):
+                counts[term] += section_weight
+        return counts
 
     def fit(self, documents: List[Tuple[str, float]]) -> None:
         """
@@ -55,34 +146,50 @@ class TFIDF:
         document_frequency is accumulated by weight so that heavily-weighted
         documents (recent / liked) push their words' IDF down (more common
         in *this* user's corpus) meaning TF-IDF will score them higher.
+
+        Documents with a NaN or infinite weight, or with no terms left
+        after stop words (e.g. an empty title and no hashtags), are
+        ignored, so they neither count towards the corpus size nor
+        produce a zero-length term frequency.
+
+        Document frequencies come from one pass over the corpus, each
+        document's terms de-duplicated through a set, so fitting is
+        linear in the total number of words.
         """
         df: Dict[str, float] = defaultdict(float)
         total_weight = 0.0
 
         for text, weight in documents:
-            tokens = set(_tokenize(text))
+            if not math.isfinite(weight):
+                continue
+            tokens = set(self._terms(text))
+            if not tokens:
+                continue        # Empty title and tags: not a document at all
             for token in tokens:
                 df[token] += weight
             total_weight += weight
 
         self._corpus_size = max(total_weight, 1.0)
-        self._idf = {
-            word: math.log((self._corpus_size + 1.0) / (freq + 1.0)) + 1.0
-            for word, freq in df.items()
-        }
+        self._idf = {word: self._idf_for(freq) for word, freq in df.items()}
+
+    def _idf_for(self, freq: float) -> float:
+        """IDF of a term with weighted document frequency `freq`."""
+        if self.smooth_idf:
+            return math.log((self._corpus_size + 1.0) / (freq + 1.0)) + 1.0
+        if freq <= 0:
+            return 0.0          # Only seen in weightless documents
+        return math.log(self._corpus_size / freq)
 
     def score_document(self, text: str, weight: float = 1.0) -> Dict[str, float]:
         """
         Return {word: tfidf_score} for a single document.
         The weight scales TF so heavier documents score higher.
         """
-        tokens = _tokenize(text)
-        if not tokens:
+        tf_raw = self._weighted_counts(text)
+        max_freq = max(tf_raw.values(), default=0.0)
+        if max_freq <= 0:
             return {}
 
-        tf_raw = Counter(tokens)
-        max_freq = max(tf_raw.values())
-
         scores = {}
         for word, freq in tf_raw.items():
             tf = (freq / max_freq) * weight
@@ -94,16 +201,57 @@ class TFIDF:
     def top_words(
         self,
         documents: List[Tuple[str, float]],
-        top_n: int = 4,
+        top_n: Optional[int] = 4,
     ) -> List[Tuple[str, float]]:
         """
         Given a list of (text, weight) pairs, compute the aggregate
-        TF-IDF across all documents and return the top_n words.
+        TF-IDF across all documents and return the top_n (word, score)
+        pairs, highest score first (every term when top_n is None).
+        Equal scores are ordered alphabetically so results are stable
+        between runs.
+
+        Non-finite scores (e.g. from a NaN weight) are dropped before
+        ranking rather than scrambling the sort order.
         """
+        if self.workers > 1 and len(documents) > self.workers:
+            aggregate = self._parallel_aggregate(documents)
+        else:
+            aggregate = self._aggregate(documents)
+
+        if self.stemming:
+            aggregate = {self.surface(term): score for term, score in aggregate.items()}
+
+        finite = [(word, score) for word, score in aggregate.items() if math.isfinite(score)]
+        return sorted(finite, key=lambda x: (-x[1], x[0]))[:top_n]
+
+    def _aggregate(self, documents: List[Tuple[str, float]]) -> Dict[str, float]:
+        """{term: summed score_document score} over `documents`."""
         aggregate: Dict[str, float] = defaultdict(float)
         for text, weight in documents:
-            scores = self.score_document(text, weight)
-            for word, score in scores.items():
+            for word, score in self.score_document(text, weight).items():
                 aggregate[word] += score
+        return aggregate
 
-        return sorted(aggregate.items(), key=lambda x: x[1], reverse=True)[:top_n]
+    def _parallel_aggregate(self, documents: List[Tuple[str, float]]) -> Dict[str, float]:
+        """
+        _aggregate over `workers` contiguous chunks in separate processes,
+        merged in chunk order, along with the surface forms each chunk saw.
+        """
+        size = math.ceil(len(documents) / self.workers)
+        chunks = [documents[i : i + size] for i in range(0, len(documents), size)]
+        aggregate: Dict[str, float] = defaultdict(float)
+        with ProcessPoolExecutor(max_workers=self.workers) as pool:
+            for partial, surface in pool.map(self._chunk_scores, chunks):
+                for word, score in partial.items():
+                    aggregate[word] += score
+                for root, forms in surface.items():
+                    self._surface[root].update(forms)
+        return aggregate
+
+    def _chunk_scores(
+        self,
+        documents: List[Tuple[str, float]],
+    ) -> Tuple[Dict[str, float], Dict[str, Counter]]:
+        """Worker side of _parallel_aggregate; runs on a copy of the model."""
+        self._surface = defaultdict(Counter)        # Only this chunk's forms
+        return dict(self._aggregate(documents)), dict(self._surface)
@@ -1,33 +1,31 @@
 """
 Trending video fetcher.
-Used as the cold-start fallback when a session has no watch history.
+Used as the cold-start fallback when a session's history has no usable
+words (see FeedConfig.cold_start_terms).
 """
 
 from __future__ import annotations
 
-from typing import List, Dict, Any
+from typing import List, Dict, Any, Optional
 
 from video.search import search_videos
+from config import FEED_CONFIG
 
 
-# Broad trending search terms we rotate through for variety.
-# These are intentionally generic and safe.
-_TRENDING_QUERIES = [
-    "trending today",
-    "popular videos",
-    "most watched",
-    "viral video",
-    "best of the week",
-]
-
-
-def get_trending(max_results: int = 20) -> List[Dict[str, Any]]:
+def get_trending(
+    max_results: int = 20,
+    terms: Optional[List[str]] = None,
+) -> List[Dict[str, Any]]:
     """
-    Pull a mix of results from several broad trending queries.
+    Pull a mix of results from up to three of `terms` (default
+    FEED_CONFIG.cold_start_terms), rotated for variety.
     De-duplicate and return up to max_results.
     """
     import random
-    queries = random.sample(_TRENDING_QUERIES, k=min(3, len(_TRENDING_QUERIES)))
+    terms = FEED_CONFIG.cold_start_terms if terms is None else terms
+    if not terms:
+        return []
+    queries = random.sample(terms, k=min(3, len(terms)))
 
     seen: set = set()
     results: List[Dict[str, Any]] = []
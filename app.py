@@ -6,25 +6,42 @@ Routes:
   GET  /watch/<video_id>          Video player
   POST /api/watch_end             Record watch event (called by player.js)
   POST /api/break_complete        Reset stats after break
+  POST /api/undislike             Reverse a previous dislike
+  POST /api/tags/block            Suppress one hashtag without disliking a video
+  POST /api/tags/allow            Keep a hashtag even if disliked videos carry it
+  GET  /api/preview_dislike/<id>  Keywords a dislike of <id> would remove
   GET  /break                     Break screen
   GET  /search                    Search results
   GET  /api/feed                  JSON feed (for infinite scroll)
+  GET  /api/keywords              Current search terms as JSON (for scripts)
+  GET  /api/history/export        Download watch history as JSON
+  POST /api/history/import        Restore watch history from JSON
+  POST /api/history/remove        Delete one watch by its timestamp
   GET  /parent                    Parental dashboard
   POST /parent/set_break          Set parent break override
+  POST /parent/clear_history      Delete watch history (optionally keeping dislikes)
 """
 
+import math
 import uuid
 from datetime import datetime, timezone
 
 from flask import (
-    Flask, render_template, request, session,
+    Flask, Response, render_template, request, session,
     jsonify, redirect, url_for
 )
 
 from config import DATABASE_URI, SECRET_KEY, GUARDRAIL_CONFIG
 from database.db import init_db
-from algorithm.guardrails import record_watch, reset_after_break, get_session_summary
-from algorithm.feed import build_feed
+from algorithm.guardrails import (
+    record_watch, reset_after_break, get_session_summary, start_break, break_state,
+    break_suggestion, events as guardrail_events,
+)
+from algorithm.feed import build_feed, feed_keywords, keywords_as_json, preview_dislike
+from algorithm.history import (
+    export_history, import_history, undislike, get_blacklisted_words, clear_history,
+    block_tag, allow_tag, remove_watch,
+)
 from video.embedder import build_embed
 from video.search import search_videos
 
@@ -67,22 +84,24 @@ def create_app() -> Flask:
 
         # Check if a break is pending (set by a previous watch_end call)
         break_pending = session.pop("break_pending", False)
-        break_seconds = session.pop("break_seconds", 0)
         break_reason = session.pop("break_reason", "")
 
         return render_template(
             "watch.html",
             video=embed,
             break_pending=break_pending,
-            break_seconds=break_seconds,
             break_reason=break_reason,
         )
 
     @app.route("/break")
     def break_screen():
-        seconds = request.args.get("seconds", 180, type=int)
+        sid = get_session_id()
         reason = request.args.get("reason", "")
-        return render_template("break.html", seconds=seconds, reason=reason)
+        remaining = start_break(sid)          # Length decided server-side
+        return render_template(
+            "break.html", seconds=math.ceil(remaining), reason=reason,
+            suggestion=break_suggestion(session_id=sid),
+        )
 
     @app.route("/search")
     def search():
@@ -101,6 +120,8 @@ def create_app() -> Flask:
             "parent_dashboard.html",
             summary=summary,
             presets=presets,
+            blocked_words=get_blacklisted_words(sid),
+            events=list(reversed(guardrail_events(sid))),
         )
 
     @app.route("/parent/set_break", methods=["POST"])
@@ -121,6 +142,13 @@ def create_app() -> Flask:
 
         return redirect(url_for("parent_dashboard"))
 
+    @app.route("/parent/clear_history", methods=["POST"])
+    def parent_clear_history():
+        sid = get_session_id()
+        keep_dislikes = request.form.get("keep_dislikes") == "1"
+        clear_history(sid, keep_dislikes=keep_dislikes)
+        return redirect(url_for("parent_dashboard"))
+
     # ------------------------------------------------------------------
     # API endpoints
     # ------------------------------------------------------------------
@@ -133,21 +161,27 @@ def create_app() -> Flask:
         """
         sid = get_session_id()
         data = request.get_json(force=True) or {}
+        local_hour = data.get("local_hour")
 
         result = record_watch(
             session_id=sid,
             video_id=data.get("video_id", ""),
             video_title=data.get("video_title", ""),
             video_hashtags=data.get("video_hashtags", ""),
+            channel=data.get("channel", ""),
+            language=data.get("language") or None,
+            hour=local_hour if isinstance(local_hour, int) else None,
             watch_time_seconds=float(data.get("watch_time_seconds", 0)),
             video_duration_seconds=float(data.get("video_duration_seconds", 1)),
             liked=bool(data.get("liked", False)),
             disliked=bool(data.get("disliked", False)),
         )
 
+        if result["reason"] == "invalid_watch":
+            return jsonify(result), 400
+
         if result["break_needed"]:
             session["break_pending"] = True
-            session["break_seconds"] = result["break_seconds"]
             session["break_reason"] = result["reason"]
 
         return jsonify(result)
@@ -155,9 +189,38 @@ def create_app() -> Flask:
     @app.route("/api/break_complete", methods=["POST"])
     def api_break_complete():
         sid = get_session_id()
-        reset_after_break(sid)
+        if not reset_after_break(sid):
+            return jsonify({"status": "on_break", **break_state(sid)}), 409
+        return jsonify({"status": "ok"})
+
+    @app.route("/api/undislike", methods=["POST"])
+    def api_undislike():
+        sid = get_session_id()
+        data = request.get_json(force=True) or {}
+        changed = undislike(sid, data.get("video_id", ""))
+        return jsonify({"updated": changed})
+
+    @app.route("/api/tags/<action>", methods=["POST"])
+    def api_tag_rule(action: str):
+        rules = {"block": block_tag, "allow": allow_tag}
+        if action not in rules:
+            return jsonify({"error": "unknown action"}), 404
+        sid = get_session_id()
+        data = request.get_json(force=True) or {}
+        if not rules[action](sid, str(data.get("tag", ""))):
+            return jsonify({"error": "tag required"}), 400
         return jsonify({"status": "ok"})
 
+    @app.route("/api/preview_dislike/<video_id>")
+    def api_preview_dislike(video_id: str):
+        sid = get_session_id()
+        current = feed_keywords(sid)
+        preview = preview_dislike(sid, video_id)
+        return jsonify({
+            "keywords": preview,
+            "removed": [k for k in current if k not in preview],
+        })
+
     @app.route("/api/feed")
     def api_feed():
         """JSON endpoint for infinite scroll / AJAX feed refresh."""
@@ -165,6 +228,37 @@ def create_app() -> Flask:
         feed = build_feed(sid)
         return jsonify(feed)
 
+    @app.route("/api/keywords")
+    def api_keywords():
+        sid = get_session_id()
+        return Response(keywords_as_json(sid), mimetype="application/json")
+
+    @app.route("/api/history/export")
+    def api_history_export():
+        sid = get_session_id()
+        return jsonify(export_history(sid))
+
+    @app.route("/api/history/import", methods=["POST"])
+    def api_history_import():
+        sid = get_session_id()
+        records = request.get_json(force=True) or []
+        if not isinstance(records, list):
+            return jsonify({"error": "expected a list of watch events"}), 400
+        imported = import_history(sid, records)
+        return jsonify({"imported": imported})
+
+    @app.route("/api/history/remove", methods=["POST"])
+    def api_history_remove():
+        sid = get_session_id()
+        data = request.get_json(force=True) or {}
+        try:
+            watched_at = datetime.fromisoformat(str(data.get("watched_at", "")))
+        except ValueError:
+            return jsonify({"error": "watched_at must be an ISO timestamp"}), 400
+        if not remove_watch(sid, watched_at):
+            return jsonify({"error": "no such watch"}), 404
+        return jsonify({"status": "ok"})
+
     return app
 
 
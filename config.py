@@ -5,8 +5,9 @@ across the codebase.
 """
 
 import os
-from dataclasses import dataclass, field
-from typing import List
+from dataclasses import dataclass, field, replace
+from enum import Enum
+from typing import Dict, FrozenSet, List, Optional
 
 
 # ---------------------------------------------------------------------------
@@ -20,13 +21,75 @@ SECRET_KEY = os.environ.get("SECRET_KEY", "dev-secret-change-in-production")
 # Guardrail settings
 # ---------------------------------------------------------------------------
 
+@dataclass
+class NightPolicy:
+    """
+    Stricter limits for the hours from start_hour up to (not including)
+    end_hour, wrapping past midnight when start_hour > end_hour.  They
+    only ever tighten: a night limit looser than the daytime one is
+    ignored.
+    """
+    start_hour: int = 21                    # 9 PM
+    end_hour: int = 6                       # 6 AM
+    hard_session_limit_minutes: int = 10
+    low_attention_threshold: float = 0.4    # <40 % completion = low attention
+
+    def covers(self, hour: int) -> bool:
+        if self.start_hour <= self.end_hour:
+            return self.start_hour <= hour < self.end_hour
+        return hour >= self.start_hour or hour < self.end_hour
+
+
+@dataclass
+class BreakSuggestion:
+    """
+    What to suggest doing on a break taken from start_hour up to (not
+    including) end_hour, wrapping past midnight like NightPolicy.
+    """
+    start_hour: int
+    end_hour: int
+    message: str
+
+    def covers(self, hour: int) -> bool:
+        if self.start_hour <= self.end_hour:
+            return self.start_hour <= hour < self.end_hour
+        return hour >= self.start_hour or hour < self.end_hour
+
+
 @dataclass
 class GuardrailConfig:
     # Attention span guardrail
     min_watch_seconds: int = 5          # Shorter watches are discarded
+    max_watch_seconds: int = 24 * 3600  # Longer (or non-finite) reports are garbage
     low_attention_threshold: float = 0.25   # <25 % completion = low attention
     low_attention_session_minutes: int = 8  # Trigger break after 8 min of low-attention
     hard_session_limit_minutes: int = 20    # Always break after 20 min regardless
+    # Total across all sessions in a day; breaks don't clear it.  None = no cap
+    daily_limit_minutes: Optional[int] = None
+    # Fading focus: break early when completion keeps falling watch to watch
+    attention_decline_slope: float = -0.1   # Completion lost per watch (or worse)
+    attention_trend_min_watches: int = 5    # Watches needed before the trend counts
+    # With an alpha in (0, 1], the low-attention check compares the session's
+    # exponentially weighted attention (recent watches count most) with the
+    # threshold instead of the latest watch alone.  None = latest watch
+    attention_ewma_alpha: Optional[float] = None
+    # Doomscrolling: this many low-attention watches in a row triggers a
+    # break however short the session is so far.  0 = off
+    low_attention_streak_limit: int = 3
+    # Rapid skipping: break once at least skip_rate_limit of the watches
+    # reported this session were too short to count, over at least
+    # skip_rate_min_attempts reports.  0 attempts = off
+    skip_rate_limit: float = 0.75
+    skip_rate_min_attempts: int = 12
+    # Attention reported before anything has been watched.  0.0 reads as
+    # "no attention yet"; 1.0 would assume full engagement until shown
+    # otherwise
+    default_attention: float = 0.0
+    # Video length buckets for the weighted attention average (upper bounds,
+    # seconds): shorts <= 1 min, short <= 10 min, medium <= 30 min, long beyond
+    attention_length_buckets: List[int] = field(
+        default_factory=lambda: [60, 600, 1800]
+    )
 
     # Break length (seconds)
     break_base_seconds: int = 180       # 3 minutes base
@@ -34,15 +97,60 @@ class GuardrailConfig:
     # Hour at which break scaling begins ramping up (24h)
     evening_start_hour: int = 18        # 6 PM
     night_end_hour: int = 23            # 11 PM
+    # Small hours after midnight stay at the maximum until this hour
+    morning_end_hour: int = 5           # 5 AM
+
+    # Break screen suggestion by hour; the first window covering the hour
+    # wins, break_suggestion_default if none does
+    break_suggestions: List[BreakSuggestion] = field(default_factory=lambda: [
+        BreakSuggestion(21, 6, "It's late. Time to put the screen away and get some sleep."),
+        BreakSuggestion(6, 12, "Stretch, have a glass of water and some breakfast."),
+        BreakSuggestion(12, 18, "Why not go outside and play for a while?"),
+        BreakSuggestion(18, 21, "Help get dinner ready or read a book for a bit."),
+    ])
+    break_suggestion_default: str = "Rest your eyes and move around for a bit."
+
+    # Guardrail events kept per session for the parent dashboard; older
+    # ones are dropped first
+    event_log_max: int = 100
 
     # Parent overrides (seconds)
     parent_break_presets: List[int] = field(
         default_factory=lambda: [600, 1800, 3600]  # 10 min, 30 min, 60 min
     )
 
+    # Opt-in: tighten the session cap and attention floor at night
+    night_mode: Optional[NightPolicy] = None
+
+    def for_hour(self, hour: int) -> "GuardrailConfig":
+        """
+        This config as it applies at `hour`: a copy with the night_mode
+        limits folded in during its hours, otherwise self unchanged.
+        """
+        night = self.night_mode
+        if night is None or not night.covers(hour):
+            return self
+        return replace(
+            self,
+            hard_session_limit_minutes=min(
+                self.hard_session_limit_minutes, night.hard_session_limit_minutes
+            ),
+            low_attention_threshold=max(
+                self.low_attention_threshold, night.low_attention_threshold
+            ),
+        )
+
+    def suggestion_for_hour(self, hour: int) -> str:
+        """The break_suggestions message for `hour` (0-23)."""
+        for suggestion in self.break_suggestions:
+            if suggestion.covers(hour):
+                return suggestion.message
+        return self.break_suggestion_default
+
     def break_length_for_hour(self, hour: int, parent_override: int | None = None) -> int:
         """
         Scales break length based on time of day.
+        Midnight to morning_end_hour -> max (the hours wrap around)
         Before evening_start_hour  -> base
         Between evening and night  -> linear scale base → max
         After night_end_hour       -> max
@@ -51,6 +159,9 @@ class GuardrailConfig:
         if parent_override is not None:
             return parent_override
 
+        if hour < self.morning_end_hour:
+            return self.break_max_seconds
+
         if hour < self.evening_start_hour:
             return self.break_base_seconds
 
@@ -70,30 +181,204 @@ class GuardrailConfig:
 # Feed / algorithm settings
 # ---------------------------------------------------------------------------
 
+# A small English stopword list.  We intentionally keep it tight so that
+# meaningful short words (e.g. "art", "war", "fun") are not removed.
+DEFAULT_STOPWORDS = frozenset({
+    "the", "a", "an", "and", "or", "but", "in", "on", "at", "to",
+    "for", "of", "with", "is", "it", "this", "that", "was", "are",
+    "be", "as", "by", "from", "have", "has", "had", "not", "they",
+    "he", "she", "we", "you", "i", "my", "your", "his", "her", "its",
+    "do", "did", "will", "would", "could", "should", "may", "might",
+    "can", "been", "being", "their", "our", "all", "more", "so", "if",
+    "than", "then", "there", "when", "where", "which", "who", "what",
+    "how", "just", "up", "out", "about", "into", "through", "also",
+    "very", "much", "many", "some", "any", "one", "two", "new", "get",
+    "no", "yes", "hi", "me",
+})
+
+# Extra stop words for videos tagged with an ISO 639-1 language code.
+# They are dropped on top of the defaults, so a history mixing Italian
+# and English titles loses both "di" and "the".
+LANGUAGE_STOPWORDS: Dict[str, FrozenSet[str]] = {
+    "it": frozenset({
+        "di", "il", "la", "lo", "le", "gli", "un", "una", "uno", "e", "ed",
+        "che", "per", "con", "del", "della", "dei", "delle", "da", "al",
+        "alla", "non", "come", "si", "ma", "più", "su", "sono", "è",
+    }),
+    "es": frozenset({
+        "de", "la", "el", "los", "las", "un", "una", "y", "que", "en",
+        "con", "por", "para", "del", "al", "se", "es", "lo", "como", "más",
+        "pero", "su", "sus", "o",
+    }),
+    "fr": frozenset({
+        "le", "la", "les", "de", "des", "du", "un", "une", "et", "en",
+        "pour", "avec", "que", "qui", "dans", "sur", "au", "aux", "est",
+        "pas", "ce", "il", "elle", "ou", "par",
+    }),
+    "de": frozenset({
+        "der", "die", "das", "und", "ein", "eine", "zu", "mit", "von",
+        "den", "dem", "des", "ist", "im", "auf", "für", "nicht", "sich",
+        "es", "auch", "wie", "oder", "aus",
+    }),
+}
+
+
+class DecayMode(str, Enum):
+    """How a watch's influence fades with age (see algorithm/history.py)."""
+    EXPONENTIAL = "exponential"     # 0.5 ^ (age / half_life)
+    LINEAR = "linear"               # 1 - age / window, floored at 0
+
+
+class DislikeMode(str, Enum):
+    """How disliked words affect the hybrid keywords (see algorithm/feed.py)."""
+    HARD_BLOCK = "hard_block"       # Scale down by penalty; worst words removed
+    PENALIZE = "penalize"           # Subtract the penalty; strong likes can win
+
+
 @dataclass
 class FeedConfig:
     feed_batch_size: int = 8        # Results per hashtag batch
     num_output_words: int = 8       # Words the hybrid model outputs
     num_batches: int = 8            # One batch per output word/hashtag
     trending_fallback_count: int = 20   # Videos to fetch when history is empty
+    # Keep at most this many non-disliked watches per session, evicting
+    # the oldest.  Disliked watches are never evicted, so blocked topics
+    # stay blocked.  None = unbounded
+    max_history: Optional[int] = None
+    # Broad, safe searches used only when history has no usable words
+    cold_start_terms: List[str] = field(default_factory=lambda: [
+        "trending today",
+        "popular videos",
+        "most watched",
+        "viral video",
+        "best of the week",
+    ])
 
     # Decay settings  (○ resolved here)
     # Half-life in days: a watch N days ago has weight 0.5^(N/half_life)
     # 3 days feels right — last night still matters, last week matters less,
     # two weeks ago is mostly noise.
     watch_half_life_days: float = 3.0
+    decay_mode: DecayMode = DecayMode.EXPONENTIAL
+    # Only used by DecayMode.LINEAR: age at which a watch stops counting
+    linear_decay_window_days: float = 14.0
+    # Recency emphasis: the decay weight is raised to this power, so 2.0
+    # makes old watches fade twice as steeply (0 = no decay at all)
+    recency_multiplier: float = 1.0
+
+    # A channel making up more than this share of history has its
+    # videos down-weighted so the feed isn't one creator's vocabulary.
+    channel_dominance_share: float = 0.5
+
+    # Engagement score  (see WatchEvent.engagement_score)
+    engagement_completion_weight: float = 0.7
+    engagement_like_bonus: float = 0.3
+    # Barely-watched videos, even ones left at 0%, still count this share
+    # of their recency weight in the history.
+    engagement_floor: float = 0.1
+    # Opt-in: additionally scale each document's TF-IDF term frequency by
+    # how much of the video was watched, never below attention_floor, so
+    # words from finished videos outrank ones from skimmed videos.  The
+    # Markov side and the history weights are unaffected.
+    attention_weighted: bool = False
+    attention_floor: float = 0.1
+    # Unliked watches that stopped before this share of the video (e.g.
+    # 0.1) were abandoned, not enjoyed, and add no words to the keyword
+    # models (they still count for related videos, drift, etc.).
+    # 0.0 = keep every watch
+    abandon_ratio: float = 0.0
+    # Depth of content: also scale each watch by 1 + ln(1 + minutes long),
+    # so a finished documentary outweighs a finished 30 s short (~4.4x vs
+    # ~1.4x).  Unknown or zero lengths count 1.0
+    length_weighting: bool = False
+
+    # Replaying a video boosts its weight by watch_time / duration,
+    # up to this multiplier.
+    rewatch_boost_cap: float = 2.0
+    # Watching the same title again in a later event adds this much per
+    # repeat (also capped at rewatch_boost_cap).
+    repeat_watch_boost: float = 0.25
 
     # Markov chain
     markov_order: int = 2           # Bigram context
+    # Score the Markov side of the hybrid by one random walk, started from
+    # one of the top markov_walk_seeds TF-IDF words picked in proportion
+    # to its score and restarted from another at dead ends, instead of by
+    # total transition weight.  Ignored with diversify.  markov_seed fixes
+    # the walk's randomness (None = vary)
+    markov_walk: bool = False
+    markov_walk_seeds: int = 5
+    markov_seed: Optional[int] = None
+
+    # Per-occurrence multipliers for the title and hashtag sections of a
+    # corpus document, in both models.  Hashtags are deliberate topic
+    # labels; raise hashtag_weight to let them outrank incidental title
+    # words ("best", "ranked").
+    title_weight: float = 1.0
+    hashtag_weight: float = 1.0
+
+    # Words dropped from titles/hashtags before either model sees them
+    stop_words: FrozenSet[str] = DEFAULT_STOPWORDS
+    # Per-language extras, applied to titles of videos tagged with that language
+    language_stop_words: Dict[str, FrozenSet[str]] = field(
+        default_factory=lambda: dict(LANGUAGE_STOPWORDS)
+    )
 
     # TF-IDF
     tfidf_max_features: int = 500
     tfidf_top_n: int = 4            # Words from TF-IDF side of hybrid
+    tfidf_include_bigrams: bool = False   # Score "pasta carbonara" as one term
+    tfidf_smooth_idf: bool = True   # False = classic ln(N/df); zeroes ubiquitous words
+    # Processes for scoring the feed's TF-IDF (see TFIDF); only pays off
+    # for histories of thousands of watches.  1 = score in-process
+    tfidf_workers: int = 1
+    # feed_keyword_phrases: adjacent title words seen together in at least
+    # this many videos are searched as one phrase ("pasta carbonara")
+    phrase_min_count: int = 2
+    stemming: bool = False          # Match "recipe"/"recipes" (see algorithm/text.py)
+
+    # Disliked words: HARD_BLOCK removes the most-disliked words and any
+    # phrase containing a disliked word; PENALIZE subtracts
+    # dislike_penalty_weight * relative_penalty from each score instead.
+    dislike_mode: DislikeMode = DislikeMode.HARD_BLOCK
+    dislike_penalty_weight: float = 0.5
+    # Disliked words can be won back: each later non-disliked watch that
+    # carries one removes 1/N of its penalty, lifting it after N such
+    # watches.  0 = a dislike only fades with age
+    dislike_recovery_watches: int = 0
+    # Typo-tolerant dislikes: a keyword within fuzzy_max_distance edits
+    # (at most 2) of a disliked word, or its possessive, is treated as
+    # that word.  Words under 5 letters still need an exact match
+    fuzzy_blacklist: bool = False
+    fuzzy_max_distance: int = 1
+    # Train a second chain on disliked videos; a Markov transition is
+    # ignored (in walks and in the hybrid's Markov scores) when that
+    # chain links the current word to the candidate at least as strongly
+    # as the watch history does ("clickbait" -> "garbage")
+    avoid_disliked_sequences: bool = False
+
+    # Diversity: seed the Markov side from the top diversify_seeds TF-IDF
+    # words in turn, and let no single video supply more than
+    # diversify_max_per_video keywords (unless slots would go unfilled).
+    diversify: bool = False
+    diversify_seeds: int = 3
+    diversify_max_per_video: int = 2
 
     # Hybrid split: markov_weight + tfidf_weight should sum to 1.0
     markov_weight: float = 0.5
     tfidf_weight: float = 0.5
 
+    def stop_words_for(self, language: Optional[str]) -> FrozenSet[str]:
+        """
+        Stop words for a video in `language` ("it", "pt-BR", ...): the
+        defaults plus that language's extras.  Untagged or unknown
+        languages get just the defaults.
+        """
+        if not language:
+            return self.stop_words
+        code = language.lower().replace("_", "-").split("-")[0]
+        return self.stop_words | self.language_stop_words.get(code, frozenset())
+
 
 GUARDRAIL_CONFIG = GuardrailConfig()
 FEED_CONFIG = FeedConfig()
@@ -1,4 +1,5 @@
 from flask_sqlalchemy import SQLAlchemy
+from sqlalchemy import inspect, text
 
 db = SQLAlchemy()
 
@@ -7,3 +8,43 @@ def init_db(app):
     db.init_app(app)
     with app.app_context():
         db.create_all()
+        _add_missing_columns()
+
+
+def _add_missing_columns():
+    """
+    Bring a database from an older install up to date.  create_all only
+    creates missing tables, so a column added to a model since then is
+    added here, with its default filled into the existing rows.  Only
+    additive changes are handled; anything else needs a fresh database.
+    """
+    inspector = inspect(db.engine)
+    with db.engine.begin() as conn:
+        for table in db.metadata.sorted_tables:
+            if not inspector.has_table(table.name):
+                continue
+            present = {col["name"] for col in inspector.get_columns(table.name)}
+            for column in table.columns:
+                if column.name not in present:
+                    conn.execute(text(
+                        f"ALTER TABLE {table.name} ADD COLUMN {_column_ddl(column)}"
+                    ))
+
+
+def _column_ddl(column) -> str:
+    """`name TYPE [DEFAULT x [NOT NULL]]` for ALTER TABLE ... ADD COLUMN."""
+    ddl = f"{column.name} {column.type.compile(dialect=db.engine.dialect)}"
+    default = column.default
+    if default is not None and default.is_scalar:
+        ddl += f" DEFAULT {_sql_literal(default.arg)}"
+        if not column.nullable:
+            ddl += " NOT NULL"      # SQLite only allows NOT NULL with a default
+    return ddl
+
+
+def _sql_literal(value) -> str:
+    if isinstance(value, bool):
+        return "1" if value else "0"
+    if isinstance(value, (int, float)):
+        return repr(value)
+    return "'" + str(value).replace("'", "''") + "'"
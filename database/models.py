@@ -2,8 +2,9 @@
 SQLAlchemy ORM models.
 """
 
-from datetime import datetime
+from datetime import datetime, timezone
 from database.db import db
+from config import FEED_CONFIG
 
 
 class WatchEvent(db.Model):
@@ -16,6 +17,8 @@ class WatchEvent(db.Model):
       C  session_time_minutes  – rolling session time (minutes watched today)
       D  video_title
       E  video_hashtags        – pipe-separated string e.g. "python|coding|tutorial"
+         channel               – uploader name (used to spot over-exposure)
+         language              – ISO 639-1 code, if known (picks stop words)
       F  liked
       G  disliked
       H  hour_of_day
@@ -29,6 +32,8 @@ class WatchEvent(db.Model):
     video_id = db.Column(db.String(64), nullable=False)
     video_title = db.Column(db.String(512), nullable=False, default="")
     video_hashtags = db.Column(db.String(1024), nullable=False, default="")
+    channel = db.Column(db.String(256), nullable=False, default="")
+    language = db.Column(db.String(8), nullable=True)
 
     watch_time_seconds = db.Column(db.Float, nullable=False, default=0.0)
     video_duration_seconds = db.Column(db.Float, nullable=False, default=1.0)
@@ -46,16 +51,172 @@ class WatchEvent(db.Model):
             return 0.0
         return min(self.watch_time_seconds / self.video_duration_seconds, 1.0)
 
+    @property
+    def rewatch_factor(self) -> float:
+        """
+        Uncapped watch_time / duration.  Above 1.0 means the user replayed
+        the video; completion_ratio stays capped at 1.0 for attention maths.
+        """
+        if self.video_duration_seconds <= 0:
+            return 0.0
+        return self.watch_time_seconds / self.video_duration_seconds
+
+    def completed(self, threshold: float = 0.9) -> bool:
+        """
+        Whether the video was essentially finished: at least `threshold`
+        of it watched.  A zero-length video is never completed.
+        """
+        if self.video_duration_seconds <= 0:
+            return False
+        return self.completion_ratio >= threshold
+
+    @property
+    def engagement_score(self) -> float:
+        """
+        How strongly this watch signals interest, in [0, 1]:
+
+            disliked            -> 0.0
+            otherwise           -> 0.7 * completion_ratio
+                                   + 0.3 if liked
+                                   (clamped to 1.0)
+
+        So a liked 30 %-watched video (0.51) outranks an unliked one
+        watched 60 % (0.42), and only a liked, fully-watched video
+        reaches 1.0.  Weights come from FeedConfig.
+        """
+        if self.disliked:
+            return 0.0
+        score = FEED_CONFIG.engagement_completion_weight * self.completion_ratio
+        if self.liked:
+            score += FEED_CONFIG.engagement_like_bonus
+        return max(0.0, min(score, 1.0))
+
     @property
     def hashtag_list(self) -> list[str]:
         if not self.video_hashtags:
             return []
         return [h.strip() for h in self.video_hashtags.split("|") if h.strip()]
 
+    def to_dict(self) -> dict:
+        """Serialisable snapshot of the event (used for history export)."""
+        return {
+            "video_id": self.video_id,
+            "video_title": self.video_title,
+            "video_hashtags": self.video_hashtags,
+            "channel": self.channel,
+            "language": self.language,
+            "watch_time_seconds": self.watch_time_seconds,
+            "video_duration_seconds": self.video_duration_seconds,
+            "liked": bool(self.liked),
+            "disliked": bool(self.disliked),
+            "hour_of_day": self.hour_of_day,
+            "timestamp": self.timestamp.isoformat() if self.timestamp else None,
+        }
+
+    @classmethod
+    def build(
+        cls,
+        session_id: str,
+        video_id: str,
+        watch_time_seconds: float,
+        video_duration_seconds: float,
+        *,
+        title: str = "",
+        hashtags: str | list[str] = "",
+        channel: str = "",
+        language: str | None = None,
+        liked: bool = False,
+        disliked: bool = False,
+        hour_of_day: int = 0,
+        timestamp: datetime | None = None,
+    ) -> "WatchEvent":
+        """
+        Construct an event with sensible defaults.
+
+        `hashtags` may be a list or an already pipe-joined string, and
+        the duration is floored at 1 second.  Raises ValueError if the
+        watch is both liked and disliked.
+        """
+        if liked and disliked:
+            raise ValueError("a watch cannot be both liked and disliked")
+        if not isinstance(hashtags, str):
+            hashtags = "|".join(hashtags)
+        return cls(
+            session_id=session_id,
+            video_id=video_id,
+            video_title=title,
+            video_hashtags=hashtags,
+            channel=channel,
+            language=language or None,
+            watch_time_seconds=float(watch_time_seconds),
+            video_duration_seconds=max(float(video_duration_seconds), 1.0),
+            liked=liked,
+            disliked=disliked,
+            hour_of_day=hour_of_day,
+            timestamp=timestamp or datetime.utcnow(),
+        )
+
+    @classmethod
+    def from_dict(cls, session_id: str, data: dict) -> "WatchEvent":
+        """
+        Inverse of `to_dict`, attaching the event to `session_id`.
+
+        Imported files are untrusted, so every field is checked: text
+        fields must be strings, flags booleans (or "true"/"false", 0/1),
+        hour_of_day within 0-23, and the timestamp ISO 8601.  An aware
+        timestamp is converted to naive UTC like everything the ORM
+        stores.  Raises ValueError or TypeError for a bad record.
+        """
+        text = {
+            key: data.get(key, "")
+            for key in ("video_id", "video_title", "video_hashtags", "channel")
+        }
+        for key, value in text.items():
+            if not isinstance(value, str):
+                raise TypeError(f"{key} must be a string, got {type(value).__name__}")
+        language = data.get("language")
+        if language is not None and not isinstance(language, str):
+            raise TypeError(f"language must be a string, got {type(language).__name__}")
+        hour = data.get("hour_of_day", 0)
+        if isinstance(hour, bool) or not isinstance(hour, int) or not 0 <= hour <= 23:
+            raise ValueError(f"hour_of_day must be 0-23, got {hour!r}")
+        timestamp = data.get("timestamp")
+        if timestamp is not None:
+            if not isinstance(timestamp, str):
+                raise TypeError(f"timestamp must be ISO 8601, got {type(timestamp).__name__}")
+            timestamp = datetime.fromisoformat(timestamp)
+            if timestamp.tzinfo is not None:
+                timestamp = timestamp.astimezone(timezone.utc).replace(tzinfo=None)
+        return cls.build(
+            session_id,
+            text["video_id"],
+            float(data.get("watch_time_seconds", 0.0)),
+            float(data.get("video_duration_seconds", 1.0)),
+            title=text["video_title"],
+            hashtags=text["video_hashtags"],
+            channel=text["channel"],
+            language=language,
+            liked=_flag(data.get("liked", False)),
+            disliked=_flag(data.get("disliked", False)),
+            hour_of_day=hour,
+            timestamp=timestamp,
+        )
+
     def __repr__(self) -> str:
         return f"<WatchEvent {self.video_id} ratio={self.completion_ratio:.2f}>"
 
 
+def _flag(value) -> bool:
+    """A boolean from an imported record; ValueError for anything ambiguous."""
+    if isinstance(value, bool):
+        return value
+    if isinstance(value, int) and value in (0, 1):
+        return bool(value)
+    if isinstance(value, str) and value.strip().lower() in ("true", "false", "1", "0"):
+        return value.strip().lower() in ("true", "1")
+    raise ValueError(f"expected a boolean, got {value!r}")
+
+
 class ParentSettings(db.Model):
     """Per-session parental control overrides."""
 
@@ -65,15 +226,48 @@ class ParentSettings(db.Model):
     session_id = db.Column(db.String(64), nullable=False, unique=True)
     break_override_seconds = db.Column(db.Integer, nullable=True)  # None = use default
     pin_hash = db.Column(db.String(256), nullable=True)  # bcrypt hash of parent PIN
+    # Dislikes recorded before this no longer suppress anything; None = keep all
+    dislikes_forgiven_before = db.Column(db.DateTime, nullable=True)
 
     def __repr__(self) -> str:
         return f"<ParentSettings session={self.session_id}>"
 
 
+class TagRule(db.Model):
+    """
+    A manual per-tag override, independent of any video dislike.
+    blocked=True suppresses the tag; blocked=False allows it even if a
+    disliked video carries it.  One rule per (session, tag).
+    """
+
+    __tablename__ = "tag_rules"
+
+    id = db.Column(db.Integer, primary_key=True)
+    session_id = db.Column(db.String(64), nullable=False, index=True)
+    tag = db.Column(db.String(128), nullable=False)
+    blocked = db.Column(db.Boolean, nullable=False, default=True)
+
+    def __repr__(self) -> str:
+        action = "block" if self.blocked else "allow"
+        return f"<TagRule {action} {self.tag}>"
+
+
 class SessionStats(db.Model):
     """
     Rolling daily stats per session.
-    Reset at midnight or when a break resets them.
+    The session counters reset at midnight or when a break resets them;
+    daily_watch_minutes only resets at midnight, so breaks can't clear it.
+
+    What each reset clears:
+
+                                      reset_session  reset  reset_daily
+        total_watch_minutes                 x          x         x
+        low_attention_streak                x          x         x
+        low_attention_minutes                          x         x
+        skipped / counted watches                      x         x
+        last_reset (attention window)                  x         x
+        break_ends_at / break_flagged                  x         x
+        daily_watch_minutes                                      x
     """
 
     __tablename__ = "session_stats"
@@ -83,9 +277,66 @@ class SessionStats(db.Model):
 
     total_watch_minutes = db.Column(db.Float, default=0.0)
     low_attention_minutes = db.Column(db.Float, default=0.0)
+    daily_watch_minutes = db.Column(db.Float, default=0.0)
+    # Low-attention watches in a row; any attentive watch resets it
+    low_attention_streak = db.Column(db.Integer, default=0)
+    # Reports this session: discarded as too short vs. recorded
+    skipped_watches = db.Column(db.Integer, default=0)
+    counted_watches = db.Column(db.Integer, default=0)
     last_reset = db.Column(db.DateTime, default=datetime.utcnow)
+    # Set when the break screen is first shown; None = not on a break
+    break_ends_at = db.Column(db.DateTime, nullable=True)
+    # A recorded watch has already asked for a break this session
+    break_flagged = db.Column(db.Boolean, default=False)
+
+    # Counters saved by to_dict, with their types
+    _COUNTERS = (
+        ("total_watch_minutes", float), ("low_attention_minutes", float),
+        ("daily_watch_minutes", float), ("low_attention_streak", int),
+        ("skipped_watches", int), ("counted_watches", int),
+    )
+
+    def to_dict(self) -> dict:
+        """Serialisable snapshot of the counters and break state."""
+        data = {name: kind(getattr(self, name) or 0) for name, kind in self._COUNTERS}
+        data.update(
+            last_reset=self.last_reset.isoformat() if self.last_reset else None,
+            break_ends_at=self.break_ends_at.isoformat() if self.break_ends_at else None,
+            break_flagged=bool(self.break_flagged),
+        )
+        return data
+
+    def apply_dict(self, data: dict) -> None:
+        """Overwrite this row with a `to_dict` snapshot; missing keys count as zero."""
+        for name, kind in self._COUNTERS:
+            setattr(self, name, kind(data.get(name) or 0))
+        last_reset = data.get("last_reset")
+        break_ends_at = data.get("break_ends_at")
+        self.last_reset = datetime.fromisoformat(last_reset) if last_reset else datetime.utcnow()
+        self.break_ends_at = datetime.fromisoformat(break_ends_at) if break_ends_at else None
+        self.break_flagged = bool(data.get("break_flagged", False))
+
+    def reset_session(self):
+        """
+        The app was closed and reopened: the session clock and the
+        low-attention streak start again, but the attention history,
+        skip counts, any break and the daily total are kept.
+        """
+        self.total_watch_minutes = 0.0
+        self.low_attention_streak = 0
 
     def reset(self):
+        """End the session (after a break); the daily total is kept."""
         self.total_watch_minutes = 0.0
         self.low_attention_minutes = 0.0
+        self.low_attention_streak = 0
+        self.skipped_watches = 0
+        self.counted_watches = 0
         self.last_reset = datetime.utcnow()
+        self.break_ends_at = None
+        self.break_flagged = False
+
+    def reset_daily(self):
+        """Start a new day: clears the daily total as well as the session."""
+        self.reset()
+        self.daily_watch_minutes = 0.0
@@ -85,7 +85,12 @@
   elDoneBtn.addEventListener("click", async () => {
     elDoneBtn.disabled = true;
     try {
-      await fetch("/api/break_complete", { method: "POST" });
+      const resp = await fetch("/api/break_complete", { method: "POST" });
+      if (resp.status === 409) {
+        // Server says the break isn't over yet — show the real remaining time
+        window.location.reload();
+        return;
+      }
     } catch (e) { /* offline */ }
     window.location.href = "/";
   });
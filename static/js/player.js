@@ -99,6 +99,9 @@
       video_id:              CONFIG.videoId        || "",
       video_title:           CONFIG.videoTitle      || "",
       video_hashtags:        CONFIG.videoHashtags   || "",
+      channel:               CONFIG.videoChannel    || "",
+      language:              CONFIG.videoLanguage   || "",
+      local_hour:            new Date().getHours(),
       watch_time_seconds:    Math.round(totalWatched),
       video_duration_seconds:CONFIG.videoDuration   || video.duration || 0,
       liked:                 liked,
@@ -106,7 +109,6 @@
     };
 
     let breakNeeded    = CONFIG.breakPending || false;
-    let breakSeconds   = CONFIG.breakSeconds || 180;
     let breakReason    = "";
 
     try {
@@ -118,7 +120,6 @@
       const data = await resp.json();
       if (data.break_needed) {
         breakNeeded  = true;
-        breakSeconds = data.break_seconds;
         breakReason  = encodeURIComponent(data.reason || "");
       }
     } catch (e) {
@@ -126,8 +127,8 @@
     }
 
     if (breakNeeded) {
-      window.location.href =
-        `/break?seconds=${breakSeconds}&reason=${breakReason}`;
+      // The server decides the break length; only the reason is passed on
+      window.location.href = `/break?reason=${breakReason}`;
     } else {
       window.location.href = "/";
     }
@@ -210,6 +211,9 @@
         video_id:              CONFIG.videoId        || "",
         video_title:           CONFIG.videoTitle      || "",
         video_hashtags:        CONFIG.videoHashtags   || "",
+        channel:               CONFIG.videoChannel    || "",
+        language:              CONFIG.videoLanguage   || "",
+        local_hour:            new Date().getHours(),
         watch_time_seconds:    Math.round(totalWatched),
         video_duration_seconds:CONFIG.videoDuration   || video.duration || 0,
         liked,
@@ -7,6 +7,14 @@ import pytest
 from unittest.mock import patch, MagicMock
 
 
+@pytest.fixture(autouse=True)
+def _fresh_keyword_cache():
+    from algorithm.feed import clear_keyword_cache
+    clear_keyword_cache()
+    yield
+    clear_keyword_cache()
+
+
 MOCK_HISTORY = [
     {
         "video_id": "abc123",
@@ -50,8 +58,9 @@ def test_build_feed_returns_videos(mock_hist, mock_dis, mock_search):
 
 
 @patch("algorithm.feed.get_trending", return_value=[MOCK_VIDEO])
+@patch("algorithm.feed.has_disliked_watches", return_value=False)
 @patch("algorithm.feed.get_weighted_history", return_value=[])
-def test_empty_history_falls_back_to_trending(mock_hist, mock_trending):
+def test_empty_history_falls_back_to_trending(mock_hist, mock_has_dis, mock_trending):
     from algorithm.feed import build_feed
     feed = build_feed("empty_session")
     assert feed == [MOCK_VIDEO]
@@ -83,3 +92,657 @@ def test_disliked_signals_suppress_words(mock_hist, mock_dis, mock_search):
     disliked = {"python": 99.0, "coding": 99.0, "tutorial": 99.0}
     keywords = _hybrid_keywords(corpus, disliked, n=8)
     assert "python" not in keywords
+
+
+def test_phrases_with_disliked_word_are_dropped():
+    from algorithm.feed import _hybrid_keywords, _build_corpus
+    from config import FEED_CONFIG
+    corpus = _build_corpus(MOCK_HISTORY)
+    with patch.object(FEED_CONFIG, "tfidf_include_bigrams", True):
+        keywords = _hybrid_keywords(corpus, {"advanced": 1.0}, n=40)
+    phrases = [k for k in keywords if " " in k]
+    assert phrases
+    assert not any("advanced" in k.split() for k in phrases)
+
+
+def test_dominant_channel_is_down_weighted():
+    from algorithm.feed import _build_corpus
+    history = [
+        {"title": f"Chef video {i}", "hashtags": ["cooking"], "channel": "ChefTV",
+         "weight": 1.0}
+        for i in range(9)
+    ] + [
+        {"title": "Space documentary", "hashtags": ["space"], "channel": "Astro",
+         "weight": 1.0},
+    ]
+    corpus = _build_corpus(history)
+    chef_weight = corpus[0][1]
+    astro_weight = corpus[-1][1]
+    assert chef_weight == pytest.approx(0.5 / 0.9)
+    assert astro_weight == 1.0
+
+
+def test_similar_in_history_ranks_by_overlap():
+    from algorithm.feed import _similar_in_history
+    history = MOCK_HISTORY + [{
+        "video_id": "ghi000",
+        "title": "Sourdough Bread Baking",
+        "hashtags": ["baking", "bread"],
+        "liked": False,
+        "weight": 0.8,
+        "completion": 0.9,
+    }]
+    assert _similar_in_history(history, "abc123", n=5) == ["def456"]
+    assert _similar_in_history(history, "missing", n=5) == []
+
+
+def test_stemmed_dislike_blocks_singular_form():
+    from algorithm.feed import _hybrid_keywords
+    from config import FEED_CONFIG
+    corpus = [("easy pasta recipe | cooking", 1.0), ("pasta carbonara | italian", 2.0)]
+    with patch.object(FEED_CONFIG, "stemming", True):
+        keywords = _hybrid_keywords(corpus, {"recipes": 99.0}, n=10)
+    assert "recipe" not in keywords
+    assert "pasta" in keywords
+
+
+def test_attention_weighting_reorders_tfidf_terms():
+    from algorithm.feed import _history_keywords
+    from config import FEED_CONFIG
+    def entry(title, weight, completion):
+        return {"video_id": title, "title": title, "hashtags": [], "channel": "",
+                "liked": False, "weight": weight, "completion": completion}
+    # The skim still weighs more after engagement (e.g. it's newer)
+    history = [entry("Volcano Eruption", 1.0, 0.05), entry("Glacier Melting", 0.6, 1.0)]
+
+    def top_terms():
+        with patch.object(FEED_CONFIG, "markov_weight", 0.0), \
+                patch.object(FEED_CONFIG, "tfidf_weight", 1.0):
+            return _history_keywords(history, {}, n=4)
+
+    assert top_terms()[:2] == ["eruption", "volcano"]
+    with patch.object(FEED_CONFIG, "attention_weighted", True):
+        assert top_terms()[:2] == ["glacier", "melting"]
+
+
+def test_keyword_result_records_sources():
+    from algorithm.feed import _hybrid_keyword_result, _build_corpus, Source
+    result = _hybrid_keyword_result(_build_corpus(MOCK_HISTORY), {}, n=8)
+    assert result.terms
+    # Markov never scores the opening word of a document; TF-IDF does
+    assert result.source_of("advanced") is Source.TFIDF
+    assert result.source_of("python") is Source.BOTH
+    assert result.source_of("not-a-term") is None
+    assert set(result.tfidf_terms) | set(result.markov_terms) == set(result.terms)
+
+
+def test_cooccurring_tags_counts_shared_videos():
+    from algorithm.feed import _cooccurring_tags
+    history = MOCK_HISTORY + [{
+        "video_id": "ghi000",
+        "title": "Pandas in Ten Minutes",
+        "hashtags": ["Python", "pandas"],
+        "weight": 0.5,
+    }]
+    assert _cooccurring_tags(history, "python", {}, n=2) == [
+        ("pandas", 2), ("coding", 1)]
+    assert ("pandas", 2) not in _cooccurring_tags(history, "python", {"pandas": 1.0})
+    assert _cooccurring_tags(history, "knitting", {}) == []
+    # Imported tags may keep their "#"; dislikes never do
+    tagged = [{"video_id": "x", "title": "Prank", "hashtags": ["#Python", "#Viral"]}]
+    assert _cooccurring_tags(tagged, "#python", {}) == [("viral", 1)]
+    assert _cooccurring_tags(tagged, "python", {"viral": 1.0}) == []
+
+
+def test_cooccurrence_graph_edges_are_undirected_and_sorted():
+    from algorithm.feed import _cooccurrence_graph
+    history = MOCK_HISTORY + [{
+        "video_id": "ghi000",
+        "title": "Pandas in Ten Minutes",
+        "hashtags": ["pandas", "Python", "python"],
+        "weight": 0.5,
+    }]
+    graph = _cooccurrence_graph(history, {})
+    assert graph == sorted(graph)
+    assert ("pandas", "python", 2) in graph
+    assert ("coding", "python", 1) in graph
+    assert all(a < b for a, b, _ in graph)
+    assert len(graph) == 6                  # 3 pairs per video, one shared
+    pruned = _cooccurrence_graph(history, {"python": 1.0})
+    assert all("python" not in (a, b) for a, b, _ in pruned)
+    assert ("coding", "tutorial", 1) in pruned
+    tagged = [{"video_id": "x", "title": "Prank", "hashtags": ["#Viral", "#Prank", "fun"]}]
+    assert _cooccurrence_graph(tagged, {"viral": 1.0}) == [("fun", "prank", 1)]
+
+
+def test_term_sources_maps_terms_to_videos():
+    from algorithm.feed import _term_sources
+    sources = _term_sources(MOCK_HISTORY, ["python", "pandas", "data science", "science guide"])
+    assert sources["python"] == ["abc123", "def456"]
+    assert sources["pandas"] == ["def456"]
+    assert sources["data science"] == ["def456"]
+    assert sources["science guide"] == ["def456"]
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"coding": 99.0})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_explain_keywords_covers_every_keyword(mock_hist, mock_dis):
+    from algorithm.feed import explain_keywords
+    explained = explain_keywords("test_session", n=5)
+    assert len(explained) == 5
+    assert "coding" not in explained
+    assert all(explained.values())
+
+
+@pytest.mark.parametrize("fraction", [0.0, 1.0])
+def test_markov_fraction_extremes_use_one_model(fraction):
+    from algorithm.feed import _hybrid_keyword_result, _build_corpus
+    result = _hybrid_keyword_result(_build_corpus(MOCK_HISTORY), {}, n=6,
+                                    markov_fraction=fraction)
+    assert 0 < len(result.terms) <= 6
+    if fraction == 0.0:
+        assert result.markov_terms == [] and result.tfidf_terms == result.terms
+    else:
+        assert result.tfidf_terms == [] and result.markov_terms == result.terms
+
+
+def test_markov_fraction_out_of_range():
+    from algorithm.feed import _hybrid_keywords, _build_corpus
+    with pytest.raises(ValueError):
+        _hybrid_keywords(_build_corpus(MOCK_HISTORY), {}, markov_fraction=1.5)
+
+
+def test_odd_keyword_count_fills_every_slot():
+    from algorithm.feed import _hybrid_keywords, _build_corpus
+    keywords = _hybrid_keywords(_build_corpus(MOCK_HISTORY), {}, n=7)
+    assert len(keywords) == 7
+    assert len(set(keywords)) == 7
+
+
+@pytest.mark.parametrize("n", [0, -1])
+def test_no_keyword_slots_returns_empty(n):
+    from algorithm.feed import _hybrid_keywords, _build_corpus
+    assert _hybrid_keywords(_build_corpus(MOCK_HISTORY), {}, n=n) == []
+
+
+def test_language_tag_selects_extra_stop_words():
+    from algorithm.feed import _build_corpus
+    history = [
+        {"title": "Pasta di Nonna, the Best", "hashtags": ["pasta"], "language": "it",
+         "weight": 1.0},
+        {"title": "Pasta di Nonna", "hashtags": ["pasta"], "weight": 1.0},
+    ]
+    tagged, untagged = [text for text, _ in _build_corpus(history)]
+    assert tagged == "pasta nonna best | pasta"
+    assert untagged.startswith("Pasta di Nonna")
+
+
+@patch("algorithm.history.ParentSettings")
+@patch("algorithm.history.TagRule")
+@patch("algorithm.history.db")
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_preview_dislike_is_read_only(mock_hist, mock_db, mock_rule, mock_settings):
+    from algorithm.feed import preview_dislike
+    from database.models import WatchEvent
+    target = WatchEvent.build("test_session", "abc123", 200, 240,
+                              title="Advanced Python Tutorial",
+                              hashtags=["python", "coding", "tutorial"])
+    with patch("algorithm.history.WatchEvent") as mock_model:
+        query = mock_model.query.filter_by.return_value.filter.return_value
+        query.all.side_effect = [[], [target]]      # no dislikes yet, then the target
+        mock_rule.query.filter_by.return_value.all.return_value = []
+        mock_settings.query.filter_by.return_value.first.return_value = None
+        keywords = preview_dislike("test_session", "abc123", n=8)
+
+    assert "coding" not in keywords and "advanced" not in keywords
+    assert "pandas" in keywords
+    assert target.disliked is False
+    mock_db.session.commit.assert_not_called()
+    mock_db.session.add.assert_not_called()
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"datascience": 1.0})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_next_word_candidates_skip_disliked(mock_hist, mock_dis):
+    from algorithm.feed import next_word_candidates
+    assert next_word_candidates("test_session", "python") == ["coding", "tutorial", "data"]
+
+
+@patch("algorithm.feed.get_disliked_history", return_value=[
+    {"video_id": "x1", "title": "Python Tutorial Scam", "hashtags": [], "weight": 1.0},
+])
+@patch("algorithm.feed.get_disliked_signals", return_value={})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_avoid_disliked_sequences_skips_disliked_followers(mock_hist, mock_dis, mock_avoid):
+    """"python tutorial" weighs more in the disliked title than in the history."""
+    from algorithm.feed import clear_keyword_cache, next_word_candidates
+    from config import FEED_CONFIG
+    assert "tutorial" in next_word_candidates("test_session", "python")
+    with patch.object(FEED_CONFIG, "avoid_disliked_sequences", True):
+        clear_keyword_cache()
+        assert "tutorial" not in next_word_candidates("test_session", "python")
+
+
+def test_avoid_chain_removes_markov_keywords():
+    from algorithm.feed import _hybrid_keyword_result
+    from algorithm.markov import MarkovChain
+    corpus = [("clickbait garbage", 1.0), ("clickbait explained", 1.0)]
+
+    def markov_terms(disliked_titles):
+        markov = MarkovChain(order=1)
+        markov.train(corpus)
+        if disliked_titles:
+            markov.avoid = MarkovChain(order=1)
+            markov.avoid.train(disliked_titles)
+        return _hybrid_keyword_result(corpus, {}, n=5, markov_fraction=1.0,
+                                      markov=markov).terms
+
+    assert "garbage" in markov_terms([])
+    assert markov_terms([("clickbait garbage compilation", 1.0)]) == ["explained"]
+
+
+def test_markov_walk_starts_from_a_top_tfidf_word():
+    from algorithm.feed import _hybrid_keyword_result
+    from algorithm.markov import MarkovChain
+    from config import FEED_CONFIG
+    corpus = [("minecraft redstone tutorial", 3.0), ("cooking pasta recipe", 1.0)]
+
+    def walk_terms(seed):
+        markov = MarkovChain.with_seed(seed, order=1)
+        markov.train(corpus)
+        return _hybrid_keyword_result(corpus, {}, n=3, markov_fraction=1.0,
+                                      markov=markov).terms
+
+    with patch.object(FEED_CONFIG, "markov_walk", True), \
+            patch.object(FEED_CONFIG, "markov_walk_seeds", 1):
+        terms = walk_terms(7)
+        assert walk_terms(7) == terms
+    assert terms[0] in {"minecraft", "redstone", "tutorial"}
+
+
+def test_markov_walk_restarts_from_another_seed_at_a_dead_end():
+    from algorithm.feed import _hybrid_keyword_result
+    from algorithm.markov import MarkovChain
+    from config import FEED_CONFIG
+    corpus = [("minecraft redstone", 3.0), ("cooking pasta", 2.0)]
+    markov = MarkovChain.with_seed(3, order=1)
+    markov.train(corpus)
+    with patch.object(FEED_CONFIG, "markov_walk", True):
+        terms = _hybrid_keyword_result(corpus, {}, n=4, markov_fraction=1.0,
+                                       markov=markov).terms
+    assert sorted(terms) == ["cooking", "minecraft", "pasta", "redstone"]
+
+
+def test_markov_walk_leaves_a_cycle_promptly():
+    from algorithm.feed import _hybrid_keyword_result
+    from algorithm.markov import MarkovChain
+    from config import FEED_CONFIG
+    corpus = [("ping pong ping pong ping", 3.0), ("cooking pasta", 1.0)]
+    markov = MarkovChain.with_seed(1, order=1)
+    markov.train(corpus)
+    with patch.object(FEED_CONFIG, "markov_walk", True), \
+            patch.object(markov, "_next_word", wraps=markov._next_word) as step:
+        terms = _hybrid_keyword_result(corpus, {}, n=4, markov_fraction=1.0,
+                                       markov=markov).terms
+    assert sorted(terms) == ["cooking", "pasta", "ping", "pong"]
+    assert step.call_count <= 6
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"tutorial": 1.0})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_feed_keyword_result_explains_the_session_keywords(mock_hist, mock_dis):
+    from algorithm.feed import Source, feed_keyword_result, feed_keywords
+    result = feed_keyword_result("test_session")
+    assert result.terms == feed_keywords("test_session")
+    assert "tutorial" not in result.terms
+    assert result.source_of("python") is Source.BOTH
+    assert all(result.source_of(t) is not None for t in result.terms)
+    mock_hist.return_value = []
+    assert feed_keyword_result("test_session").terms == []
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_keywords_are_cached_until_history_changes(mock_hist, mock_dis):
+    from algorithm.feed import feed_keywords
+    from algorithm.history import mark_history_changed
+    first = feed_keywords("test_session")
+    assert feed_keywords("test_session") == first
+    assert mock_hist.call_count == 1
+
+    mark_history_changed("test_session")
+    assert feed_keywords("test_session") == first
+    assert mock_hist.call_count == 2
+
+
+class TestDislikeModes:
+    SCORES = {"pasta": 1.0, "viral": 0.4, "pasta carbonara": 0.6, "space": 0.3}
+    DISLIKED = {"viral": 2.0, "pasta": 1.0}
+
+    def test_hard_block_drops_worst_word_and_phrases(self):
+        from algorithm.feed import _apply_dislikes
+        from config import DislikeMode
+        result = _apply_dislikes(self.SCORES, self.DISLIKED, DislikeMode.HARD_BLOCK)
+        assert set(result) == {"pasta", "space"}
+        assert result["pasta"] == pytest.approx(0.5)
+
+    def test_hard_block_removes_a_top_scoring_max_penalty_word(self):
+        from algorithm.feed import _apply_dislikes
+        from config import DislikeMode
+        result = _apply_dislikes({"recipe": 1.0}, {"recipe": 99.0}, DislikeMode.HARD_BLOCK)
+        assert result == {}
+
+    def test_penalize_lets_strong_likes_survive(self):
+        from algorithm.feed import _apply_dislikes
+        from config import DislikeMode, FEED_CONFIG
+        weight = FEED_CONFIG.dislike_penalty_weight
+        result = _apply_dislikes(self.SCORES, self.DISLIKED, DislikeMode.PENALIZE)
+        assert result["pasta"] == pytest.approx(1.0 - weight * 0.5)
+        assert result["pasta carbonara"] == pytest.approx(0.6 - weight * 0.5)
+        assert "viral" not in result
+        assert result["space"] == 0.3
+
+
+DOMINANT_CORPUS = [
+    ("Creamy pasta carbonara sauce recipe | pasta carbonara", 5.0),
+    ("Beginner guitar chords lesson | guitar", 1.0),
+    ("Soccer dribbling drills | soccer", 1.0),
+]
+
+
+def test_diversify_caps_terms_per_video():
+    from algorithm.feed import _hybrid_keywords
+    plain = _hybrid_keywords(DOMINANT_CORPUS, {}, n=4)
+    assert {"carbonara", "pasta", "sauce", "recipe"} == set(plain)
+    diverse = _hybrid_keywords(DOMINANT_CORPUS, {}, n=4, diversify=True)
+    assert len(diverse) == len(set(diverse)) == 4
+    assert {"guitar", "soccer"} <= set(diverse)
+
+
+def test_diversify_still_fills_every_slot():
+    from algorithm.feed import _hybrid_keywords
+    assert len(_hybrid_keywords(DOMINANT_CORPUS, {}, n=12, diversify=True)) == 12
+
+
+def test_round_robin_walks_alternate_seeds():
+    from algorithm.feed import _round_robin_walks
+    from algorithm.markov import MarkovChain
+    chain = MarkovChain(order=1)
+    chain.train([("pasta sauce recipe", 1.0), ("guitar chords", 1.0)])
+    words = [w for w, _ in _round_robin_walks(chain, ["pasta", "guitar"])]
+    assert words == ["pasta", "guitar", "sauce", "chords", "recipe"]
+
+
+FADED_HISTORY = [
+    {"video_id": "a", "title": "Funny kittens compilation", "hashtags": ["cats"],
+     "liked": False, "weight": 0.0, "completion": 0.1, "channel": ""},
+    {"video_id": "b", "title": "Guitar for kids", "hashtags": [],
+     "liked": False, "weight": 0.0, "completion": 0.1, "channel": ""},
+]
+
+
+@patch("algorithm.feed.get_trending")
+@patch("algorithm.feed.search_videos", return_value=[MOCK_VIDEO])
+@patch("algorithm.feed.get_disliked_signals", return_value={"funny": 1.0})
+@patch("algorithm.feed.get_weighted_history", return_value=FADED_HISTORY)
+def test_faded_history_falls_back_to_title_words(mock_hist, mock_dis, mock_search,
+                                                 mock_trending):
+    from algorithm.feed import build_feed, feed_keywords
+    assert feed_keywords("sess", n=3) == ["kittens", "compilation", "guitar"]
+    assert build_feed("sess")
+    mock_trending.assert_not_called()
+
+
+@patch("algorithm.trending.search_videos", return_value=[MOCK_VIDEO])
+def test_trending_uses_configured_cold_start_terms(mock_search):
+    from algorithm.trending import get_trending
+    from config import FEED_CONFIG
+    with patch.object(FEED_CONFIG, "cold_start_terms", ["cartoons"]):
+        assert get_trending(max_results=5) == [MOCK_VIDEO]
+    mock_search.assert_called_once_with("cartoons", max_results=5)
+    assert get_trending(terms=[]) == []
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"prank": 2.0})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_rank_candidates_orders_by_profile(mock_hist, mock_dis):
+    from algorithm.feed import rank_candidates
+    python = {"id": "p", "title": "Python Pandas Tips", "hashtags": ["python"]}
+    unrelated = {"id": "u", "title": "Knitting Basics", "hashtags": []}
+    prank = {"id": "x", "title": "Prank Compilation", "hashtags": []}
+    ranked = rank_candidates("sess", [unrelated, prank, python])
+    assert [video["id"] for video, _ in ranked] == ["p", "u", "x"]
+    scores = dict((video["id"], score) for video, score in ranked)
+    assert scores["u"] == 0.0
+    assert scores["x"] < 0.0
+
+
+ITALIAN_HISTORY = [
+    {"video_id": "c1", "title": "Pasta Carbonara", "hashtags": ["pasta", "italian"],
+     "weight": 1.0},
+    {"video_id": "c2", "title": "Italian Pizza Dough", "hashtags": ["italian", "pizza"],
+     "weight": 0.8},
+]
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"prank": 2.0})
+@patch("algorithm.feed.get_weighted_history", return_value=ITALIAN_HISTORY)
+def test_explain_candidate_matches_rank_candidates(mock_hist, mock_dis):
+    from algorithm.feed import explain_candidate, rank_candidates
+    video = {"id": "v", "title": "Italian Pasta Prank", "hashtags": ["pasta"]}
+    why = explain_candidate("sess", video)
+    terms = dict(why["terms"])
+    assert terms["pasta"] > 0 and terms["italian"] > 0
+    assert terms["prank"] < 0
+    assert why["blocked"] == ["prank"]
+    assert [s for _, s in why["terms"]] == sorted((s for _, s in why["terms"]), reverse=True)
+    [(_, score)] = rank_candidates("sess", [video])
+    assert sum(terms.values()) == pytest.approx(score)
+
+
+@pytest.mark.parametrize("history, has_dislikes, disliked, reason", [
+    ([], False, {}, "no_history"),
+    ([], True, {"prank": 2.0}, "all_disliked"),
+    (FADED_HISTORY, True, {w: 1.0 for w in ("funny", "kittens", "compilation", "guitar", "kids")},
+     "all_disliked"),
+    ([dict(FADED_HISTORY[0], title="Go Go")], False, {}, "no_usable_words"),
+])
+def test_cold_start_reasons(history, has_dislikes, disliked, reason):
+    from algorithm.feed import try_feed_keywords, feed_keywords, ColdStartError
+    with patch("algorithm.feed.get_weighted_history", return_value=history), \
+         patch("algorithm.feed.has_disliked_watches", return_value=has_dislikes), \
+         patch("algorithm.feed.get_disliked_signals", return_value=disliked):
+        with pytest.raises(ColdStartError) as raised:
+            try_feed_keywords("sess")
+        assert feed_keywords("sess") == []
+    assert raised.value.reason.value == reason
+
+
+RECENT_HISTORY = [      # Newest first, as get_weighted_history returns it
+    {"video_id": "n1", "title": "Guitar Chords", "hashtags": ["guitar"], "weight": 1.0},
+    {"video_id": "n2", "title": "Guitar Prank", "hashtags": ["guitar"], "weight": 0.9},
+    {"video_id": "o1", "title": "Pasta Carbonara", "hashtags": ["pasta"], "weight": 0.5},
+    {"video_id": "o2", "title": "Pasta Pesto", "hashtags": ["pasta"], "weight": 0.5},
+]
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"prank": 1.0})
+@patch("algorithm.feed.get_weighted_history", return_value=RECENT_HISTORY)
+def test_recent_top_words_only_see_the_window(mock_hist, mock_dis):
+    from algorithm.feed import recent_top_words
+    recent = recent_top_words("sess", window=2, n=5)
+    assert recent[0] == "guitar"
+    assert "pasta" not in recent and "prank" not in recent
+    assert "pasta" in recent_top_words("sess", window=50, n=10)
+    assert recent_top_words("sess", window=0) == []
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"prank": 1.0})
+@patch("algorithm.feed.get_weighted_history", return_value=RECENT_HISTORY)
+def test_profile_vector_matches_top_words(mock_hist, mock_dis):
+    from algorithm.feed import profile_vector, recent_top_words
+    vector = profile_vector("sess")
+    assert "prank" not in vector and "guitar prank" not in vector
+    assert max(vector.values()) == pytest.approx(1.0)
+    ranked = sorted(vector, key=lambda t: (-vector[t], t))
+    assert recent_top_words("sess", window=50, n=4) == ranked[:4]
+
+    mock_hist.return_value = []
+    assert profile_vector("sess") == {}
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_keywords_as_json(mock_hist, mock_dis):
+    import json
+    from algorithm.feed import keywords_as_json
+    from config import FEED_CONFIG
+    report = json.loads(keywords_as_json("sess", n=3))
+    assert report["cold_start"] is False and report["cold_start_reason"] is None
+    assert len(report["terms"]) == 3
+    assert set(report["generated_from"]) == set(report["terms"])
+    assert all(report["generated_from"][term] for term in report["terms"])
+
+    mock_hist.return_value = []
+    with patch("algorithm.feed.has_disliked_watches", return_value=False):
+        cold = json.loads(keywords_as_json("empty"))
+    assert cold == {"terms": FEED_CONFIG.cold_start_terms, "generated_from": {},
+                    "cold_start": True, "cold_start_reason": "no_history"}
+
+
+@patch("algorithm.feed.get_trending", return_value=[MOCK_VIDEO])
+@patch("algorithm.feed.has_disliked_watches", return_value=False)
+@patch("algorithm.feed.get_disliked_signals", return_value={})
+@patch("algorithm.feed.get_weighted_history", return_value=[])
+def test_cold_start_provider_supplies_terms(mock_hist, mock_dis, mock_has, mock_trending):
+    import json
+    from algorithm.feed import build_feed, keywords_as_json, set_cold_start_provider
+    from config import FEED_CONFIG
+    provider = MagicMock(return_value=["science fair", "lego"])
+    set_cold_start_provider(provider)
+    try:
+        assert build_feed("empty") == [MOCK_VIDEO]
+        mock_trending.assert_called_once_with(
+            max_results=FEED_CONFIG.trending_fallback_count, terms=["science fair", "lego"]
+        )
+        assert json.loads(keywords_as_json("empty"))["terms"] == ["science fair", "lego"]
+
+        provider.reset_mock()
+        mock_hist.return_value = MOCK_HISTORY
+        with patch("algorithm.feed._batch_search", return_value=[MOCK_VIDEO]):
+            build_feed("warm")
+        json.loads(keywords_as_json("warm"))
+        provider.assert_not_called()
+    finally:
+        set_cold_start_provider(None)
+
+
+def test_plugged_in_scorer_ranks_the_tfidf_side():
+    from algorithm.feed import _build_corpus, _hybrid_keywords, set_term_scorer
+
+    class FixedScorer:
+        def score_terms(self, documents, blacklist=frozenset()):
+            return [("pandas", 1.0), ("guide", 0.5)]
+
+    corpus = _build_corpus(MOCK_HISTORY)
+    set_term_scorer(FixedScorer())
+    try:
+        assert _hybrid_keywords(corpus, {}, n=2, markov_fraction=0.0) == ["pandas", "guide"]
+    finally:
+        set_term_scorer(None)
+    assert _hybrid_keywords(corpus, {}, n=1, markov_fraction=0.0) == ["python"]
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={"pandas": 2.0})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_bm25_top_words_skip_disliked(mock_hist, mock_dis):
+    from algorithm.feed import bm25_top_words
+    words = bm25_top_words("sess", n=4)
+    assert len(words) == 4
+    assert "pandas" not in words
+    assert bm25_top_words("sess", n=0) == []
+
+
+def test_hybrid_merge_is_deterministic():
+    from algorithm.feed import _hybrid_keywords
+    corpus = [("zebra mango apple kiwi | fruit", 1.0)]
+    tied = _hybrid_keywords(corpus, {}, n=5, markov_fraction=0.0)
+    assert tied == sorted(tied)                     # Equal scores go alphabetically
+    assert _hybrid_keywords(corpus, {}, n=5) == _hybrid_keywords(corpus, {}, n=5)
+
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={})
+@patch("algorithm.feed.get_weighted_history", return_value=MOCK_HISTORY)
+def test_markov_chain_is_reused_until_history_changes(mock_hist, mock_dis):
+    import algorithm.feed as feed
+    from algorithm.history import mark_history_changed
+    before = feed._markov_builds
+    first = feed.feed_keywords("chain_sess", n=8)
+    feed.feed_keywords("chain_sess", n=4)               # Keyword cache miss, same chain
+    feed.next_word_candidates("chain_sess", "python")
+    assert feed._markov_builds == before + 1
+
+    mark_history_changed("chain_sess")
+    assert feed.feed_keywords("chain_sess", n=8) == first
+    assert feed._markov_builds == before + 2
+
+
+CARBONARA_HISTORY = [
+    {"video_id": "c1", "title": "Pasta Carbonara Recipe", "hashtags": ["pasta"], "weight": 1.0},
+    {"video_id": "c2", "title": "Easy Pasta Carbonara", "hashtags": ["dinner"], "weight": 0.9},
+    {"video_id": "c3", "title": "Pizza Dough Basics", "hashtags": ["pizza"], "weight": 0.8},
+]
+
+
+@patch("algorithm.feed.get_disliked_signals", return_value={})
+@patch("algorithm.feed.get_weighted_history", return_value=CARBONARA_HISTORY)
+def test_keyword_phrases_join_repeated_pairs(mock_hist, mock_dis):
+    from algorithm.feed import feed_keyword_phrases
+    terms = feed_keyword_phrases("sess", n=4)
+    assert len(terms) == 4
+    assert "pasta carbonara" in terms
+    assert "pasta" not in terms and "carbonara" not in terms    # Used up by the phrase
+    assert "pizza dough" not in terms                           # Only seen once
+
+    mock_dis.return_value = {"carbonara": 2.0}
+    assert not any("carbonara" in t for t in feed_keyword_phrases("sess", n=4))
+
+
+def test_fuzzy_blacklist_blocks_near_misses():
+    from algorithm.feed import _hybrid_keywords
+    from config import FEED_CONFIG
+    corpus = [
+        ("Carbonaraa Night | dinner", 1.0),
+        ("Grandma's Carbonara's Secret | dinner", 1.0),
+        ("Pasta Night | dinner", 1.0),
+    ]
+    disliked = {"carbonara": 2.0}
+    assert "carbonaraa" in _hybrid_keywords(corpus, disliked, n=10)
+    with patch.object(FEED_CONFIG, "fuzzy_blacklist", True):
+        terms = _hybrid_keywords(corpus, disliked, n=10)
+    assert "carbonaraa" not in terms and "carbonara's" not in terms
+    assert "pasta" in terms and "night" in terms
+
+
+def _topic(video_id, title, tag):
+    return {"video_id": video_id, "title": title, "hashtags": [tag], "weight": 1.0}
+
+
+SWITCHED_HISTORY = [                    # Newest first
+    _topic("g1", "Minecraft Speedrun", "gaming"),
+    _topic("g2", "Minecraft Redstone Build", "gaming"),
+    _topic("c1", "Pasta Carbonara", "cooking"),
+    _topic("c2", "Pasta Bake", "cooking"),
+]
+
+
+@patch("algorithm.feed.get_weighted_history", return_value=SWITCHED_HISTORY)
+def test_interest_drift_spots_a_topic_switch(mock_hist):
+    from algorithm.feed import interest_drift
+    assert interest_drift("sess", recent_window=2) == pytest.approx(1.0)
+    assert interest_drift("sess", recent_window=3) < 1.0     # Both sides share "pasta"
+    assert interest_drift("sess", recent_window=4) == 0.0    # Nothing before the window
+    mock_hist.return_value = SWITCHED_HISTORY[:2] * 2
+    assert interest_drift("sess", recent_window=2) == pytest.approx(0.0, abs=1e-9)
+
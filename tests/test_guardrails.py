@@ -3,6 +3,8 @@ Tests for the guardrail system.
 Runs without a database by mocking the ORM calls.
 """
 
+from datetime import datetime, timedelta
+
 import pytest
 from unittest.mock import MagicMock, patch
 from config import GuardrailConfig
@@ -26,6 +28,11 @@ class TestBreakLengthForHour:
         length = cfg.break_length_for_hour(20)
         assert cfg.break_base_seconds < length < cfg.break_max_seconds
 
+    def test_small_hours_return_max(self, cfg):
+        assert cfg.break_length_for_hour(0) == cfg.break_max_seconds
+        assert cfg.break_length_for_hour(1) > cfg.break_length_for_hour(14)
+        assert cfg.break_length_for_hour(cfg.morning_end_hour) == cfg.break_base_seconds
+
     def test_parent_override_takes_priority(self, cfg):
         override = 1800
         assert cfg.break_length_for_hour(10, parent_override=override) == override
@@ -39,6 +46,27 @@ class TestBreakLengthForHour:
             assert lengths[i] <= lengths[i + 1]
 
 
+class TestBreakSuggestion:
+    def test_message_follows_the_hour(self, cfg):
+        from algorithm.guardrails import break_suggestion
+        assert "sleep" in break_suggestion(2, cfg)
+        assert "outside" in break_suggestion(15, cfg)
+
+    def test_messages_are_configurable(self):
+        from algorithm.guardrails import break_suggestion
+        from config import BreakSuggestion
+        cfg = GuardrailConfig(break_suggestions=[BreakSuggestion(22, 7, "Hora de dormir.")],
+                              break_suggestion_default="Hora de descansar.")
+        assert break_suggestion(2, cfg) == "Hora de dormir."
+        assert break_suggestion(15, cfg) == "Hora de descansar."
+
+    def test_default_hour_is_the_sessions_local_hour(self, cfg):
+        from algorithm.guardrails import break_suggestion
+        with patch("algorithm.guardrails._local_hour", return_value=2) as local:
+            assert break_suggestion(cfg=cfg, session_id="sess1") == break_suggestion(2, cfg)
+        local.assert_called_once_with("sess1")
+
+
 class TestRecordWatch:
     """record_watch should correctly classify events and set break flags."""
 
@@ -46,7 +74,13 @@ class TestRecordWatch:
         stats = MagicMock()
         stats.total_watch_minutes   = total_min
         stats.low_attention_minutes = low_att_min
+        stats.daily_watch_minutes   = total_min
+        stats.low_attention_streak  = 0
+        stats.break_flagged         = False
+        stats.skipped_watches       = 0
+        stats.counted_watches       = 0
         stats.last_reset = __import__("datetime").datetime(2024, 1, 1)
+        stats.break_ends_at = None
         return stats
 
     @patch("algorithm.guardrails._get_or_create_stats")
@@ -55,6 +89,7 @@ class TestRecordWatch:
     @patch("algorithm.guardrails.db")
     def test_short_watch_is_discarded(self, mock_db, mock_po, mock_reset, mock_stats):
         from algorithm.guardrails import record_watch
+        mock_stats.return_value = self._make_stats()
         result = record_watch(
             "sess1", "vidA", "Title", "tag1",
             watch_time_seconds=3,       # < 5 sec
@@ -63,6 +98,54 @@ class TestRecordWatch:
         assert result["recorded"] is False
         assert result["break_needed"] is False
 
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_watch_at_threshold_is_counted(self, mock_db, mock_po, mock_reset, mock_stats_fn):
+        from algorithm.guardrails import record_watch
+        mock_stats_fn.return_value = self._make_stats()
+        result = record_watch(
+            "sess1", "vidA", "Title", "tag1",
+            watch_time_seconds=GuardrailConfig().min_watch_seconds,
+            video_duration_seconds=120,
+        )
+        assert result["recorded"] is True
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_custom_min_watch_threshold(self, mock_db, mock_po, mock_reset, mock_stats_fn):
+        from algorithm.guardrails import record_watch
+        mock_stats_fn.return_value = self._make_stats()
+        result = record_watch(
+            "sess1", "vidA", "Title", "tag1",
+            watch_time_seconds=6,
+            video_duration_seconds=120,
+            cfg=GuardrailConfig(min_watch_seconds=7),
+        )
+        assert result["recorded"] is False
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_liked_and_disliked_is_rejected(self, mock_db, mock_po, mock_reset, mock_stats_fn):
+        """A contradictory watch must never be stored, so it can't feed dislike penalties."""
+        from algorithm.guardrails import record_watch
+        mock_stats_fn.return_value = self._make_stats()
+        result = record_watch(
+            "sess1", "vidA", "Title", "tag1",
+            watch_time_seconds=60,
+            video_duration_seconds=120,
+            liked=True,
+            disliked=True,
+        )
+        assert result["recorded"] is False
+        assert result["reason"] == "invalid_watch"
+        mock_db.session.add.assert_not_called()
+
     @patch("algorithm.guardrails._get_or_create_stats")
     @patch("algorithm.guardrails._reset_if_new_day")
     @patch("algorithm.guardrails._get_parent_override", return_value=None)
@@ -115,3 +198,700 @@ class TestRecordWatch:
         )
         assert result["recorded"] is True
         assert result["break_needed"] is False
+
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_recorded_watch_marks_history_changed(self, mock_db, mock_po, mock_reset, mock_stats_fn):
+        from algorithm.guardrails import record_watch
+        from algorithm.history import history_version
+        mock_stats_fn.return_value = self._make_stats()
+        before = history_version("sess-version")
+        record_watch("sess-version", "vidC", "Title", "tag1",
+                     watch_time_seconds=90, video_duration_seconds=120)
+        assert history_version("sess-version") == before + 1
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_local_hour_sets_break_length(self, mock_db, mock_po, mock_reset, mock_stats_fn):
+        from algorithm.guardrails import record_watch
+        cfg = GuardrailConfig()
+        lengths = {}
+        for hour in (10, 23, 99):
+            mock_stats_fn.return_value = self._make_stats(total_min=21.0)
+            result = record_watch("sess1", "vidA", "Title", "tag1",
+                                  watch_time_seconds=60, video_duration_seconds=120,
+                                  hour=hour)
+            lengths[hour] = result["break_seconds"]
+        assert lengths[10] == cfg.break_base_seconds
+        assert lengths[23] == cfg.break_max_seconds
+        assert lengths[99] in {cfg.break_length_for_hour(h) for h in range(24)}
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_garbage_watch_time_is_rejected(self, mock_db, mock_po, mock_reset, mock_stats_fn):
+        from algorithm.guardrails import record_watch
+        stats = self._make_stats(total_min=5.0)
+        mock_stats_fn.return_value = stats
+        for garbage in (1e12, float("inf"), float("nan")):
+            result = record_watch("sess1", "vidA", "Title", "tag1",
+                                  watch_time_seconds=garbage, video_duration_seconds=120)
+            assert result["recorded"] is False
+            assert result["reason"] == "watch_too_long"
+        assert stats.total_watch_minutes == 5.0
+        mock_db.session.add.assert_not_called()
+
+class TestEvaluateBreak:
+    """evaluate_break should honour whatever GuardrailConfig it is given."""
+
+    def test_default_config_under_limits(self):
+        from algorithm.guardrails import evaluate_break
+        assert evaluate_break(10.0, 0.0, 0.8) == (False, "")
+
+    def test_stricter_config_breaks_sooner(self):
+        from algorithm.guardrails import evaluate_break
+        strict = GuardrailConfig(hard_session_limit_minutes=5)
+        needed, reason = evaluate_break(10.0, 0.0, 0.8, strict)
+        assert needed is True
+        assert "5 min" in reason
+
+    def test_custom_attention_floor(self):
+        from algorithm.guardrails import evaluate_break
+        lenient = GuardrailConfig(low_attention_threshold=0.1)
+        # 20 % completion is low by default but fine under a 10 % floor
+        assert evaluate_break(10.0, 9.0, 0.2)[0] is True
+        assert evaluate_break(10.0, 9.0, 0.2, lenient)[0] is False
+
+    def test_limits_are_exclusive(self):
+        from algorithm.guardrails import evaluate_break
+        cfg = GuardrailConfig(hard_session_limit_minutes=30,
+                              low_attention_session_minutes=10,
+                              low_attention_threshold=0.3)
+        assert evaluate_break(30.0, 0.0, 0.8, cfg)[0] is False
+        assert evaluate_break(30.5, 0.0, 0.8, cfg)[0] is True
+        # Exactly at the attention floor, or exactly at the minutes limit, is fine
+        assert evaluate_break(12.0, 12.0, 0.3, cfg)[0] is False
+        assert evaluate_break(12.0, 10.0, 0.2, cfg)[0] is False
+        assert evaluate_break(12.0, 10.5, 0.2, cfg)[0] is True
+
+
+class TestAttentionTrend:
+    """A steady slide in completion should trigger a break before the average dips."""
+
+    def test_too_few_points_is_flat(self):
+        from algorithm.guardrails import attention_trend
+        assert attention_trend([]) == 0.0
+        assert attention_trend([0.4]) == 0.0
+
+    def test_slope_per_watch(self):
+        from algorithm.guardrails import attention_trend
+        assert attention_trend([1.0, 0.8, 0.6, 0.4]) == pytest.approx(-0.2)
+        assert attention_trend([0.5, 0.5, 0.5]) == 0.0
+
+    def test_declining_attention_breaks_early(self, cfg):
+        from algorithm.guardrails import evaluate_break
+        fading = [1.0, 0.9, 0.7, 0.6, 0.45]     # average 0.73, well above 25 %
+        needed, reason = evaluate_break(6.0, 0.0, 0.45, cfg, attention_scores=fading)
+        assert needed is True
+        assert "falling" in reason
+        # Not enough watches yet to call it a trend
+        assert evaluate_break(6.0, 0.0, 0.6, cfg, attention_scores=fading[:4])[0] is False
+
+
+class TestEwmaAttention:
+    """Recent focus should count for more than the start of the session."""
+
+    def test_recent_low_scores_dominate(self):
+        from algorithm.guardrails import average_attention, ewma_attention
+        scores = [1.0, 1.0, 1.0, 0.1, 0.1, 0.1]
+        assert ewma_attention(scores, alpha=0.5) < 0.25
+        assert ewma_attention(list(reversed(scores)), alpha=0.5) > 0.75
+        assert ewma_attention(scores, alpha=1.0) == pytest.approx(0.1)
+        assert ewma_attention([]) == 0.0
+        with pytest.raises(ValueError):
+            ewma_attention(scores, alpha=0.0)
+        watches = [MagicMock(completion_ratio=s) for s in scores]
+        assert average_attention(watches) == pytest.approx(0.55)     # Flat mean unchanged
+
+    def test_optional_for_the_low_attention_check(self):
+        from algorithm.guardrails import evaluate_break
+        scores = [0.1] * 5 + [0.3]           # Latest watch alone is above 25 %
+        plain = GuardrailConfig()
+        smoothed = GuardrailConfig(attention_ewma_alpha=0.3)
+        assert evaluate_break(10.0, 9.0, 0.3, plain, attention_scores=scores)[0] is False
+        needed, reason = evaluate_break(10.0, 9.0, 0.3, smoothed, attention_scores=scores)
+        assert needed is True and "Low attention (16%" in reason
+
+
+class TestWeightedAttention:
+    """Length buckets keep a pile of shorts from drowning out long videos."""
+
+    def _watch(self, watched, duration):
+        from database.models import WatchEvent
+        return WatchEvent.build("sess1", "vid", watched, duration)
+
+    def test_empty_is_zero(self):
+        from algorithm.guardrails import average_attention, weighted_attention
+        assert average_attention([]) == 0.0
+        assert weighted_attention([]) == 0.0
+
+    def test_empty_uses_configured_default(self):
+        from algorithm.guardrails import average_attention, weighted_attention
+        hopeful = GuardrailConfig(default_attention=1.0)
+        assert average_attention([], hopeful) == 1.0
+        assert weighted_attention([], hopeful) == 1.0
+        watched = [MagicMock(completion_ratio=0.5)]
+        assert average_attention(watched, hopeful) == 0.5     # Only used without data
+
+    def test_buckets_are_averaged_equally(self, cfg):
+        from algorithm.guardrails import average_attention, weighted_attention
+        shorts = [self._watch(30, 30) for _ in range(9)]
+        documentary = self._watch(600, 2400)                # 10 of 40 min
+        watches = shorts + [documentary]
+        assert average_attention(watches) == pytest.approx(0.925)
+        assert weighted_attention(watches, cfg) == pytest.approx((1.0 + 0.25) / 2)
+
+    def test_watch_time_weights_within_a_bucket(self, cfg):
+        from algorithm.guardrails import weighted_attention
+        # Both long videos: 30 min at 75 % outweighs 2 min at 5 %
+        watches = [self._watch(1800, 2400), self._watch(120, 2400)]
+        expected = (0.75 * 1800 + 0.05 * 120) / (1800 + 120)
+        assert weighted_attention(watches, cfg) == pytest.approx(expected)
+
+
+class TestLowAttentionStreak:
+    """Several skimmed videos in a row is a doomscrolling signal on its own."""
+
+    def _record(self, seconds, cfg):
+        from algorithm.guardrails import record_watch
+        return record_watch("sess1", "vidA", "Title", "tag1",
+                            watch_time_seconds=seconds, video_duration_seconds=300,
+                            cfg=cfg)
+
+    @patch("algorithm.guardrails._current_stats")
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_streak_breaks_and_resets(self, mock_db, mock_po, mock_stats_fn, mock_current):
+        from database.models import SessionStats
+        from algorithm.guardrails import consecutive_low_attention
+        cfg = GuardrailConfig(low_attention_streak_limit=3)
+        stats = SessionStats(session_id="sess1")
+        mock_stats_fn.return_value = mock_current.return_value = stats
+
+        for _ in range(2):
+            assert self._record(30, cfg)["break_needed"] is False    # 10 %
+        assert consecutive_low_attention("sess1") == 2
+
+        assert self._record(240, cfg)["break_needed"] is False       # 80 %
+        assert consecutive_low_attention("sess1") == 0
+
+        self._record(30, cfg)
+        self._record(30, cfg)
+        result = self._record(30, cfg)
+        assert result["break_needed"] is True
+        assert "3 low-attention videos in a row" in result["reason"]
+        assert consecutive_low_attention("sess1") == 3
+
+    def test_zero_limit_disables_streak(self):
+        from algorithm.guardrails import evaluate_break
+        off = GuardrailConfig(low_attention_streak_limit=0)
+        assert evaluate_break(2.0, 2.0, 0.1, off, low_streak=10) == (False, "")
+
+
+class TestSkipRate:
+    """Flicking past video after video is caught even though skips aren't recorded."""
+
+    @patch("algorithm.guardrails._current_stats")
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_rapid_skipping_breaks(self, mock_db, mock_po, mock_stats_fn, mock_current):
+        from database.models import SessionStats
+        from algorithm.guardrails import record_watch, skip_rate, RecordOutcome
+        cfg = GuardrailConfig(skip_rate_limit=0.75, skip_rate_min_attempts=8,
+                              low_attention_streak_limit=0)
+        stats = SessionStats(session_id="sess1")
+        mock_stats_fn.return_value = mock_current.return_value = stats
+
+        def watch(seconds):
+            return record_watch("sess1", "vidA", "Title", "tag1",
+                                watch_time_seconds=seconds, video_duration_seconds=300,
+                                cfg=cfg)
+
+        assert watch(240)["recorded"] is True
+        assert watch(240)["recorded"] is True
+        results = [watch(2) for _ in range(6)]     # 6 of 8 skipped on the last one
+        assert all(r["recorded"] is False for r in results)
+        assert [r["break_needed"] for r in results] == [False] * 5 + [True]
+        assert results[-1]["outcome"] is RecordOutcome.BREAK_TRIGGERED
+        assert "Skipped 6 of 8" in results[-1]["reason"]
+        assert results[0]["reason"] == "watch_too_short"
+        assert skip_rate("sess1") == pytest.approx(0.75)
+        assert mock_db.session.add.call_count == 2     # Only the two real watches
+
+    def test_too_few_attempts_or_off(self):
+        from algorithm.guardrails import evaluate_break
+        cfg = GuardrailConfig(skip_rate_min_attempts=12)
+        assert evaluate_break(2.0, 0.0, 0.9, cfg, skipped=10, counted=1) == (False, "")
+        off = GuardrailConfig(skip_rate_min_attempts=0)
+        assert evaluate_break(2.0, 0.0, 0.9, off, skipped=50, counted=0) == (False, "")
+
+    def test_reset_clears_counts(self):
+        from database.models import SessionStats
+        stats = SessionStats(session_id="sess1", skipped_watches=4, counted_watches=3)
+        stats.reset()
+        assert (stats.skipped_watches, stats.counted_watches) == (0, 0)
+
+
+class TestNightMode:
+    """An opt-in night policy tightens the limits only during its hours."""
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_break_comes_sooner_at_night(self, mock_db, mock_po, mock_stats_fn):
+        from database.models import SessionStats
+        from algorithm.guardrails import record_watch
+        from config import NightPolicy
+        cfg = GuardrailConfig(night_mode=NightPolicy(start_hour=21, end_hour=6,
+                                                     hard_session_limit_minutes=10))
+
+        def watches_until_break(hour):
+            mock_stats_fn.return_value = SessionStats(session_id="sess1")
+            for count in range(1, 20):
+                result = record_watch("sess1", "vidA", "Title", "tag1",
+                                      watch_time_seconds=240, video_duration_seconds=300,
+                                      hour=hour, cfg=cfg)
+                if result["break_needed"]:
+                    return count
+            return None
+
+        day, night = watches_until_break(14), watches_until_break(2)
+        assert (day, night) == (6, 3)       # 4 min each: over 20 min vs. over 10 min
+
+    def test_policy_hours_and_tightening(self):
+        from config import NightPolicy
+        night = NightPolicy(start_hour=21, end_hour=6, low_attention_threshold=0.4,
+                            hard_session_limit_minutes=30)
+        assert [night.covers(h) for h in (20, 21, 0, 5, 6)] == [False, True, True, True, False]
+        cfg = GuardrailConfig(night_mode=night)
+        assert cfg.for_hour(14) is cfg
+        late = cfg.for_hour(23)
+        assert late.low_attention_threshold == 0.4
+        assert late.hard_session_limit_minutes == cfg.hard_session_limit_minutes  # Never loosened
+        assert GuardrailConfig().for_hour(2) == GuardrailConfig()
+
+
+class TestEventLog:
+    """Breaks, daily limits and misclicks are logged, each once."""
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_break_is_logged_once_per_trigger(self, mock_db, mock_po, mock_stats_fn):
+        from database.models import SessionStats
+        from algorithm.guardrails import (
+            GuardrailEventKind, clear_events, events, record_watch,
+        )
+        mock_stats_fn.return_value = SessionStats(session_id="log_sess")
+        clear_events("log_sess")
+        record_watch("log_sess", "vidA", "Title", "tag1", watch_time_seconds=2,
+                     video_duration_seconds=300, hour=14)
+        for _ in range(8):              # Break due from the 6th; still due after
+            record_watch("log_sess", "vidA", "Title", "tag1", watch_time_seconds=240,
+                         video_duration_seconds=300, hour=14)
+
+        log = events("log_sess")
+        assert [e.kind for e in log] == [GuardrailEventKind.MISCLICK, GuardrailEventKind.BREAK]
+        assert log[1].session_minutes == pytest.approx(24.0)
+        assert log[1].attention == pytest.approx(0.8)
+        assert "Hard session limit" in log[1].reason
+        clear_events("log_sess")
+        assert events("log_sess") == []
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_daily_limit_break_is_logged_as_such(self, mock_db, mock_po, mock_stats_fn):
+        from database.models import SessionStats
+        from algorithm.guardrails import GuardrailEventKind, clear_events, events, record_watch
+        stats = SessionStats(session_id="daily_sess")
+        stats.daily_watch_minutes = 58.0
+        mock_stats_fn.return_value = stats
+        clear_events("daily_sess")
+        record_watch("daily_sess", "vidA", "Title", "tag1", watch_time_seconds=240,
+                     video_duration_seconds=300, hour=14,
+                     cfg=GuardrailConfig(daily_limit_minutes=60))
+        assert [e.kind for e in events("daily_sess")] == [GuardrailEventKind.DAILY_LIMIT]
+        clear_events("daily_sess")
+
+    def test_log_keeps_only_the_newest_events(self):
+        from database.models import SessionStats
+        from algorithm.guardrails import GuardrailEventKind, _log_event, clear_events, events
+        from config import GUARDRAIL_CONFIG
+        stats = SessionStats(session_id="cap_sess")
+        clear_events("cap_sess")
+        with patch.object(GUARDRAIL_CONFIG, "event_log_max", 3):
+            for n in range(5):
+                _log_event("cap_sess", GuardrailEventKind.MISCLICK, stats, 0.0, reason=str(n))
+        assert [e.reason for e in events("cap_sess")] == ["2", "3", "4"]
+        clear_events("cap_sess")
+
+
+class TestRecordOutcome:
+    """Only the watch that first needs a break reports BREAK_TRIGGERED."""
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_transition_fires_once(self, mock_db, mock_po, mock_stats_fn):
+        from database.models import SessionStats
+        from algorithm.guardrails import record_watch, reset_after_break, RecordOutcome
+        cfg = GuardrailConfig(hard_session_limit_minutes=10)
+        mock_stats_fn.return_value = SessionStats(session_id="sess1")
+
+        def watch():
+            return record_watch("sess1", "vidA", "Title", "tag1",
+                                watch_time_seconds=240, video_duration_seconds=300,
+                                cfg=cfg)["outcome"]
+
+        outcomes = [watch() for _ in range(5)]      # 4 min each: over 10 min on the 3rd
+        assert outcomes == [RecordOutcome.COUNTED] * 2 + [RecordOutcome.BREAK_TRIGGERED] \
+            + [RecordOutcome.COUNTED] * 2
+        assert record_watch("sess1", "vidA", "Title", "tag1", 1, 300,
+                            cfg=cfg)["outcome"] is RecordOutcome.IGNORED
+
+        stats = mock_stats_fn.return_value
+        assert reset_after_break("sess1") is False      # Break due but never taken
+        stats.break_ends_at = datetime.utcnow() - timedelta(seconds=1)
+        assert reset_after_break("sess1") is True
+        outcomes = [watch() for _ in range(3)]
+        assert outcomes.count(RecordOutcome.BREAK_TRIGGERED) == 1
+
+
+class TestDailyLimit:
+    """The daily cap counts every session and is not cleared by breaks."""
+
+    def _record(self, cfg):
+        from algorithm.guardrails import record_watch
+        return record_watch("sess1", "vidA", "Title", "tag1",
+                            watch_time_seconds=240, video_duration_seconds=300,
+                            cfg=cfg)
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_daily_cap_trips_across_sessions(self, mock_db, mock_po, mock_stats_fn):
+        from database.models import SessionStats
+        from algorithm.guardrails import daily_limit_reached, reset_after_break
+        cfg = GuardrailConfig(daily_limit_minutes=30)
+        mock_stats_fn.return_value = SessionStats(session_id="sess1")
+
+        # Two 12-minute sessions with a break between: under every limit
+        for _ in range(2):
+            for _ in range(3):
+                assert self._record(cfg)["break_needed"] is False
+            mock_stats_fn.return_value.break_ends_at = datetime.utcnow() - timedelta(seconds=1)
+            assert reset_after_break("sess1") is True
+        assert daily_limit_reached("sess1", cfg) is False
+
+        # The third session reaches 30 min for the day after 6 minutes
+        self._record(cfg)
+        result = self._record(cfg)
+        assert result["break_needed"] is True
+        assert "Daily limit" in result["reason"]
+        assert daily_limit_reached("sess1", cfg) is True
+
+    def test_no_daily_limit_by_default(self, cfg):
+        from algorithm.guardrails import evaluate_break
+        assert cfg.daily_limit_minutes is None
+        assert evaluate_break(0.0, 0.0, 1.0, cfg, daily_minutes=1000.0) == (False, "")
+
+    def test_reset_daily_clears_both_counters(self):
+        from database.models import SessionStats
+        stats = SessionStats(session_id="sess1", total_watch_minutes=12.0,
+                             daily_watch_minutes=40.0)
+        stats.reset()
+        assert stats.total_watch_minutes == 0.0
+        assert stats.daily_watch_minutes == 40.0
+        stats.reset_daily()
+        assert stats.daily_watch_minutes == 0.0
+
+
+class TestProjectBreakTime:
+    """Forecast of the watching time left before the next session-limit break."""
+
+    def _project(self, total, daily=0.0, **cfg):
+        from database.models import SessionStats
+        from algorithm.guardrails import project_break_time
+        stats = SessionStats(session_id="sess1", total_watch_minutes=total,
+                             daily_watch_minutes=daily)
+        with patch("algorithm.guardrails._current_stats", return_value=stats):
+            return project_break_time("sess1", GuardrailConfig(**cfg), hour=14)
+
+    def test_fifteen_minutes_in_leaves_five(self):
+        assert self._project(15.0) == pytest.approx(300.0)
+        assert self._project(20.0) == 0.0           # The limit is exclusive
+        assert self._project(21.0) is None
+
+    def test_daily_limit_can_come_first(self):
+        assert self._project(5.0, daily=58.0, daily_limit_minutes=60) == pytest.approx(120.0)
+        assert self._project(5.0, daily=60.0, daily_limit_minutes=60) is None
+
+    def test_default_hour_is_the_viewers_local_hour(self):
+        from config import NightPolicy
+        from database.models import SessionStats
+        from algorithm.guardrails import project_break_time
+        cfg = GuardrailConfig(night_mode=NightPolicy(start_hour=21, end_hour=6))
+        stats = SessionStats(session_id="sess1", total_watch_minutes=5.0,
+                             daily_watch_minutes=5.0)
+        with patch("algorithm.guardrails._current_stats", return_value=stats), \
+                patch("algorithm.guardrails._local_hour", return_value=22):
+            assert project_break_time("sess1", cfg) == pytest.approx(300.0)   # Night limit
+
+
+class TestResets:
+    """Each reset clears exactly its row of the SessionStats table."""
+
+    STARTED = datetime(2024, 1, 1, 9, 0)
+    BREAK_ENDS = datetime(2024, 1, 1, 10, 0)
+
+    def _busy_stats(self):
+        from database.models import SessionStats
+        return SessionStats(
+            session_id="sess1", total_watch_minutes=12.0, low_attention_minutes=5.0,
+            daily_watch_minutes=40.0, low_attention_streak=2, skipped_watches=3,
+            counted_watches=7, last_reset=self.STARTED, break_ends_at=self.BREAK_ENDS,
+            break_flagged=True,
+        )
+
+    @staticmethod
+    def _fields(stats):
+        return (stats.total_watch_minutes, stats.low_attention_streak,
+                stats.low_attention_minutes, stats.skipped_watches, stats.counted_watches,
+                stats.last_reset, stats.break_ends_at, stats.break_flagged,
+                stats.daily_watch_minutes)
+
+    def test_reset_session(self):
+        stats = self._busy_stats()
+        stats.reset_session()
+        assert self._fields(stats) == (0.0, 0, 5.0, 3, 7, self.STARTED, self.BREAK_ENDS,
+                                       True, 40.0)
+
+    def test_reset(self):
+        stats = self._busy_stats()
+        stats.reset()
+        fields = self._fields(stats)
+        assert fields[:5] == (0.0, 0, 0.0, 0, 0)
+        assert fields[5] > self.STARTED
+        assert fields[6:] == (None, False, 40.0)
+
+    def test_reset_daily(self):
+        stats = self._busy_stats()
+        stats.reset_daily()
+        fields = self._fields(stats)
+        assert fields[:5] == (0.0, 0, 0.0, 0, 0)
+        assert fields[6:] == (None, False, 0.0)
+
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails.db")
+    def test_reset_session_commits(self, mock_db, mock_stats_fn, mock_reset):
+        from algorithm.guardrails import reset_session
+        stats = mock_stats_fn.return_value = self._busy_stats()
+        reset_session("sess1")
+        assert (stats.total_watch_minutes, stats.daily_watch_minutes) == (0.0, 40.0)
+        mock_db.session.commit.assert_called_once()
+
+
+class TestStatsJson:
+    """Guardrail counters survive a save / load through JSON."""
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails.db")
+    def test_round_trip_today(self, mock_db, mock_stats_fn, tmp_path):
+        from database.models import SessionStats
+        from algorithm.guardrails import load_stats_json, save_stats_json
+        path = str(tmp_path / "stats.json")
+        saved = SessionStats(session_id="sess1", total_watch_minutes=12.5,
+                             low_attention_minutes=3.0, daily_watch_minutes=41.0,
+                             low_attention_streak=2, skipped_watches=1, counted_watches=6,
+                             last_reset=datetime.utcnow(), break_flagged=True)
+        mock_stats_fn.return_value = saved
+        save_stats_json("sess1", path)
+
+        loaded = mock_stats_fn.return_value = SessionStats(session_id="sess1")
+        assert load_stats_json("sess1", path) is True
+        assert loaded.to_dict() == saved.to_dict()
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails.db")
+    def test_stale_or_missing_file_starts_fresh(self, mock_db, mock_stats_fn, tmp_path):
+        import json
+        from database.models import SessionStats
+        from algorithm.guardrails import load_stats_json
+        path = tmp_path / "stats.json"
+        stats = mock_stats_fn.return_value = SessionStats(session_id="sess1")
+        assert load_stats_json("sess1", str(path)) is False
+        mock_stats_fn.assert_not_called()
+
+        path.write_text(json.dumps({"daily_watch_minutes": 55.0, "total_watch_minutes": 9.0,
+                                    "last_reset": "2024-01-01T09:00:00"}))
+        assert load_stats_json("sess1", str(path)) is True
+        assert (stats.daily_watch_minutes, stats.total_watch_minutes) == (0.0, 0.0)
+        assert stats.last_reset.date() == datetime.utcnow().date()
+
+
+class TestBreakState:
+    """Breaks are tracked server-side once the break screen is shown."""
+
+    def _make_stats(self, break_ends_at=None):
+        from database.models import SessionStats
+        stats = SessionStats(session_id="sess1", total_watch_minutes=25.0,
+                             low_attention_minutes=0.0)
+        stats.break_ends_at = break_ends_at
+        return stats
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_start_break_sets_timer(self, mock_db, mock_po, mock_stats_fn):
+        from algorithm.guardrails import start_break, break_state
+        mock_stats_fn.return_value = self._make_stats()
+        assert start_break("sess1", hour=10) == GuardrailConfig().break_base_seconds
+        state = break_state("sess1")
+        assert state["state"] == "on_break"
+        assert 170 < state["remaining_seconds"] <= 180
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=900)
+    @patch("algorithm.guardrails.db")
+    def test_break_length_is_decided_server_side(self, mock_db, mock_po, mock_stats_fn):
+        from algorithm.guardrails import start_break
+        mock_stats_fn.return_value = self._make_stats()
+        with patch("algorithm.guardrails._local_hour", return_value=23):
+            assert start_break("sess1") == 900.0           # Parent override wins
+        mock_po.return_value = None
+        mock_stats_fn.return_value = self._make_stats()
+        with patch("algorithm.guardrails._local_hour", return_value=23):
+            assert start_break("sess1") == GuardrailConfig().break_max_seconds
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails.db")
+    def test_reset_refused_until_break_elapses(self, mock_db, mock_stats_fn):
+        from datetime import datetime, timedelta
+        from algorithm.guardrails import reset_after_break
+        stats = self._make_stats(datetime.utcnow() + timedelta(seconds=60))
+        mock_stats_fn.return_value = stats
+        assert reset_after_break("sess1") is False
+        assert stats.total_watch_minutes == 25.0
+
+        stats.break_ends_at = datetime.utcnow() - timedelta(seconds=1)
+        assert reset_after_break("sess1") is True
+        assert stats.total_watch_minutes == 0.0
+        assert stats.break_ends_at is None
+        assert reset_after_break("sess1") is False      # That break is used up
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails.db")
+    def test_reset_refused_without_a_break(self, mock_db, mock_stats_fn):
+        from algorithm.guardrails import reset_after_break
+        stats = self._make_stats()
+        stats.daily_watch_minutes = 25.0
+        stats.last_reset = datetime(2024, 1, 1)
+        mock_stats_fn.return_value = stats
+        before = stats.to_dict()
+        assert reset_after_break("sess1") is False
+        assert stats.to_dict() == before
+        mock_db.session.commit.assert_not_called()
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails.db")
+    def test_break_clears_session_but_not_the_day(self, mock_db, mock_po, mock_stats_fn):
+        from algorithm.guardrails import reset_after_break, record_watch
+        cfg = GuardrailConfig(hard_session_limit_minutes=20)
+        stats = self._make_stats()
+        stats.daily_watch_minutes = 25.0
+        stats.low_attention_streak = 2
+        stats.break_ends_at = datetime.utcnow() - timedelta(seconds=1)
+        mock_stats_fn.return_value = stats
+
+        assert reset_after_break("sess1") is True
+        assert (stats.total_watch_minutes, stats.low_attention_streak) == (0.0, 0)
+        assert stats.daily_watch_minutes == 25.0
+
+        result = record_watch("sess1", "vidA", "Title", "tag1",
+                              watch_time_seconds=240, video_duration_seconds=300,
+                              cfg=cfg)
+        assert result["break_needed"] is False
+
+    @patch("algorithm.guardrails._get_or_create_stats")
+    @patch("algorithm.guardrails._reset_if_new_day")
+    @patch("algorithm.guardrails.db")
+    def test_watch_during_break_is_ignored(self, mock_db, mock_reset, mock_stats_fn):
+        from datetime import datetime, timedelta
+        from algorithm.guardrails import record_watch
+        mock_stats_fn.return_value = self._make_stats(
+            datetime.utcnow() + timedelta(seconds=60))
+        result = record_watch("sess1", "vidA", "Title", "tag1",
+                              watch_time_seconds=60, video_duration_seconds=120)
+        assert result["recorded"] is False
+        assert result["reason"] == "on_break"
+        mock_db.session.add.assert_not_called()
+
+
+class TestSessionSummary:
+    """The dashboard snapshot reads state without changing it."""
+
+    @patch("algorithm.guardrails.get_blacklisted_words", return_value=["prank", "viral"])
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails._session_watches")
+    @patch("algorithm.guardrails._current_stats")
+    @patch("algorithm.guardrails.db")
+    def test_summary_reports_without_writing(self, mock_db, mock_stats_fn, mock_watches,
+                                             mock_po, mock_blocked):
+        from datetime import datetime
+        from algorithm.guardrails import get_session_summary
+        from database.models import SessionStats, WatchEvent
+        mock_stats_fn.return_value = SessionStats(
+            session_id="sess1", total_watch_minutes=21.0, low_attention_minutes=0.0,
+            daily_watch_minutes=21.0, last_reset=datetime(2024, 1, 1), break_ends_at=None)
+        mock_watches.return_value = [
+            WatchEvent.build("sess1", "a", 60, 120), WatchEvent.build("sess1", "b", 120, 120)]
+
+        summary = get_session_summary("sess1")
+        assert summary.videos_watched == 2
+        assert summary.attention_average == 0.75
+        assert summary.break_due is True        # over the 20 min hard limit
+        assert summary.blocked_terms == 2
+        assert "Blocked terms:    2" in str(summary)
+        mock_db.session.commit.assert_not_called()
+
+    @patch("algorithm.guardrails.get_blacklisted_words", return_value=[])
+    @patch("algorithm.guardrails._get_parent_override", return_value=None)
+    @patch("algorithm.guardrails._session_watches", return_value=[])
+    @patch("algorithm.guardrails._current_stats")
+    @patch("algorithm.guardrails._local_hour", return_value=23)
+    @patch("algorithm.guardrails.db")
+    def test_summary_uses_local_hour_and_flagged_break(self, mock_db, mock_hour, mock_stats_fn,
+                                                       mock_watches, mock_po, mock_blocked, cfg):
+        from algorithm.guardrails import get_session_summary
+        from database.models import SessionStats
+        stats = SessionStats(session_id="sess1", total_watch_minutes=0.0,
+                             low_attention_minutes=0.0, daily_watch_minutes=0.0,
+                             last_reset=datetime(2024, 1, 1), break_ends_at=None)
+        mock_stats_fn.return_value = stats
+
+        summary = get_session_summary("sess1")
+        assert summary.break_seconds == cfg.break_max_seconds
+        assert summary.break_due is False
+        stats.break_flagged = True          # e.g. tripped by misclick skips alone
+        assert get_session_summary("sess1").break_due is True
@@ -0,0 +1,712 @@
+"""Tests for watch history weighting and persistence."""
+
+import json
+from datetime import datetime
+from unittest.mock import MagicMock, patch
+
+import pytest
+from config import DecayMode
+from database.models import WatchEvent
+
+
+def _event(**overrides):
+    fields = dict(
+        title="Pasta Carbonara",
+        hashtags=["pasta", "italian"],
+        liked=True,
+        hour_of_day=19,
+        timestamp=datetime(2024, 1, 1, 19, 30),
+    )
+    fields.update(overrides)
+    watch_time = fields.pop("watch_time_seconds", 120.0)
+    video_id = fields.pop("video_id", "vid1")
+    return WatchEvent.build("sess1", video_id, watch_time, 240.0, **fields)
+
+
+def test_watch_event_round_trips_through_dict():
+    original = _event()
+    restored = WatchEvent.from_dict("sess2", original.to_dict())
+    assert restored.session_id == "sess2"
+    assert restored.to_dict() == original.to_dict()
+
+
+@patch("algorithm.history.import_history", return_value=1)
+@patch("algorithm.history.export_history")
+def test_save_and_load_json(mock_export, mock_import, tmp_path):
+    from algorithm.history import save_history_json, load_history_json
+    mock_export.return_value = [_event(disliked=True, liked=False).to_dict()]
+    path = tmp_path / "history.json"
+
+    save_history_json("sess1", str(path))
+    assert json.loads(path.read_text())[0]["disliked"] is True
+
+    assert load_history_json("sess2", str(path)) == 1
+    mock_import.assert_called_once_with("sess2", mock_export.return_value)
+
+
+class TestDecayWeight:
+
+    def test_exponential_halves_each_half_life(self):
+        from algorithm.history import _decay_weight
+        from config import FEED_CONFIG, DecayMode
+        half_life = FEED_CONFIG.watch_half_life_days
+        assert _decay_weight(0.0, DecayMode.EXPONENTIAL) == pytest.approx(1.0)
+        assert _decay_weight(half_life, DecayMode.EXPONENTIAL) == pytest.approx(0.5)
+
+    @pytest.mark.parametrize("mode", list(DecayMode))
+    def test_newer_watches_weigh_more(self, mode):
+        from algorithm.history import _decay_weight
+        weights = [_decay_weight(age, mode) for age in (0.0, 1.0, 2.0, 5.0)]
+        assert weights == sorted(weights, reverse=True)
+        assert weights[0] > weights[-1]
+
+    def test_linear_fades_to_zero_over_window(self):
+        from algorithm.history import _decay_weight
+        from config import FEED_CONFIG, DecayMode
+        window = FEED_CONFIG.linear_decay_window_days
+        assert _decay_weight(window / 2, DecayMode.LINEAR) == pytest.approx(0.5)
+        assert _decay_weight(window * 2, DecayMode.LINEAR) == 0.0
+
+
+class TestEngagementScore:
+
+    def test_liked_partial_watch_beats_unliked(self):
+        liked = _event(watch_time_seconds=72.0, liked=True)       # 30 %
+        plain = _event(watch_time_seconds=144.0, liked=False)     # 60 %
+        assert liked.engagement_score == pytest.approx(0.51)
+        assert liked.engagement_score > plain.engagement_score
+
+    def test_disliked_scores_zero(self):
+        ev = _event(watch_time_seconds=216.0, liked=False, disliked=True)
+        assert ev.engagement_score == 0.0
+
+    def test_score_is_clamped_to_one(self):
+        ev = _event(watch_time_seconds=500.0, liked=True)
+        assert ev.engagement_score == 1.0
+
+    def test_zero_length_video_has_zero_completion(self):
+        ev = WatchEvent(watch_time_seconds=30.0, video_duration_seconds=0.0)
+        assert ev.completion_ratio == 0.0
+        assert ev.rewatch_factor == 0.0
+
+
+def _mock_query(mock_model, events, ordered=None):
+    """Answer the filtered query with `events`; order_by() with `ordered` (default the same)."""
+    query = mock_model.query.filter_by.return_value.filter.return_value
+    query.all.return_value = events
+    query.order_by.return_value.all.return_value = events if ordered is None else ordered
+
+
+@pytest.fixture(autouse=True)
+def _no_tag_rules():
+    with patch("algorithm.history.TagRule") as mock_rule, \
+         patch("algorithm.history.ParentSettings") as mock_settings:
+        mock_rule.query.filter_by.return_value.all.return_value = []
+        mock_settings.query.filter_by.return_value.first.return_value = None
+        yield mock_rule
+
+
+@patch("algorithm.history.db")
+@patch("algorithm.history.WatchEvent")
+def test_undislike_clears_flag(mock_model, mock_db):
+    from algorithm.history import undislike
+    ev = _event(disliked=True, liked=False)
+    _mock_query(mock_model, [ev])
+    assert undislike("sess1", "vid1") == 1
+    assert ev.disliked is False
+    mock_db.session.commit.assert_called_once()
+
+
+@patch("algorithm.history.WatchEvent")
+def test_shared_dislike_words_survive_a_reversal(mock_model):
+    """After one dislike is reversed, words the other still contributes stay penalised."""
+    from algorithm.history import get_disliked_signals
+    remaining = _event(video_id="vid2", title="Viral Prank",
+                       hashtags=["viral", "prank"], disliked=True, liked=False)
+    _mock_query(mock_model, [remaining])
+    penalties = get_disliked_signals("sess1")
+    assert "viral" in penalties
+    assert "pasta" not in penalties
+
+
+@patch("algorithm.history.get_disliked_signals",
+       return_value={"viral": 2.0, "clickbait": 1.0, "prank": 0.5})
+def test_blacklisted_words_are_sorted(mock_signals):
+    from algorithm.history import get_blacklisted_words
+    assert get_blacklisted_words("sess1") == ["clickbait", "prank", "viral"]
+
+
+@patch("algorithm.history.WatchEvent")
+def test_channel_exposure_counts_watches(mock_model):
+    from algorithm.history import get_channel_exposure
+    _mock_query(mock_model, [
+        _event(channel="ChefTV"), _event(channel="ChefTV"),
+        _event(channel="Astro"), _event(channel=""),
+    ])
+    assert get_channel_exposure("sess1") == {"ChefTV": 2, "Astro": 1}
+
+
+def test_rewatched_video_is_boosted_but_completion_capped():
+    from algorithm.history import get_weighted_history
+    once = _event(video_id="once", watch_time_seconds=240.0, liked=False,
+                  timestamp=datetime.utcnow())
+    twice = _event(video_id="twice", watch_time_seconds=480.0, liked=False,
+                   timestamp=datetime.utcnow())
+    assert twice.completion_ratio == 1.0
+    assert twice.rewatch_factor == pytest.approx(2.0)
+
+    with patch("algorithm.history.WatchEvent") as mock_model:
+        _mock_query(mock_model, [once, twice])
+        history = {h["video_id"]: h for h in get_weighted_history("sess1")}
+
+    assert history["twice"]["completion"] == 1.0
+    assert history["twice"]["weight"] == pytest.approx(history["once"]["weight"] * 2, rel=1e-3)
+
+
+def test_build_fills_defaults_and_rejects_contradictions():
+    ev = WatchEvent.build("sess1", "vid1", 30, 0, hashtags=["a", "b"])
+    assert ev.video_hashtags == "a|b"
+    assert ev.video_duration_seconds == 1.0
+    assert ev.liked is False and ev.disliked is False
+    with pytest.raises(ValueError):
+        WatchEvent.build("sess1", "vid1", 30, 60, liked=True, disliked=True)
+
+
+@patch("algorithm.history.db")
+def test_import_skips_contradictory_records(mock_db):
+    from algorithm.history import import_history
+    good = _event(liked=False).to_dict()
+    bad = dict(good, liked=True, disliked=True)
+    assert import_history("sess1", [good, bad]) == 1
+    assert mock_db.session.add.call_count == 1
+
+
+@patch("algorithm.history.db")
+def test_import_skips_malformed_and_garbage_records(mock_db):
+    from algorithm.history import import_history
+    good = _event(liked=False).to_dict()
+    records = [
+        good,
+        "not a record",
+        dict(good, watch_time_seconds="soon"),
+        dict(good, hour_of_day=None),
+        dict(good, timestamp=12345),
+        dict(good, watch_time_seconds=float("inf")),
+        dict(good, watch_time_seconds=3 * 24 * 3600.0),
+        dict(good, video_title=None),
+        dict(good, hour_of_day=24),
+        dict(good, liked="maybe"),
+    ]
+    assert import_history("sess1", records) == 1
+    assert mock_db.session.add.call_count == 1
+
+
+@patch("algorithm.history.db")
+def test_import_normalises_timestamps_and_string_flags(mock_db):
+    from algorithm.history import import_history
+    base = _event(liked=False).to_dict()
+    records = [
+        dict(base, video_id="aware", timestamp="2024-01-02T09:00:00+02:00", liked="false"),
+        dict(base, video_id="naive", timestamp="2024-01-02T08:00:00", disliked="true"),
+        dict(base, video_id="zulu", timestamp="2024-01-02T06:30:00+00:00", liked=1),
+    ]
+    assert import_history("sess1", records) == 3
+    added = [c.args[0] for c in mock_db.session.add.call_args_list]
+    assert [ev.video_id for ev in added] == ["zulu", "aware", "naive"]   # Oldest first
+    assert all(ev.timestamp.tzinfo is None for ev in added)
+    assert added[1].timestamp == datetime(2024, 1, 2, 7, 0)
+    assert [(ev.liked, ev.disliked) for ev in added] == [(True, False), (False, False),
+                                                         (False, True)]
+
+
+@patch("algorithm.history.db")
+@patch("algorithm.history.WatchEvent")
+def test_clear_history_keeps_dislikes(mock_model, mock_db):
+    from algorithm.history import clear_history, get_blacklisted_words
+    watched = _event(video_id="vid1")
+    _mock_query(mock_model, [watched])
+    assert clear_history("sess1", keep_dislikes=True) == 1
+    mock_db.session.delete.assert_called_once_with(watched)
+
+    # A fresh dislike overlapping a kept one doesn't duplicate its words
+    _mock_query(mock_model, [
+        _event(video_id="vid2", hashtags=["viral"], title="Viral Prank",
+               liked=False, disliked=True),
+        _event(video_id="vid3", hashtags=["viral"], title="Viral Dance",
+               liked=False, disliked=True),
+    ])
+    assert get_blacklisted_words("sess1") == ["dance", "prank", "viral"]
+
+
+@patch("algorithm.history.db")
+@patch("algorithm.history.WatchEvent")
+def test_clear_everything_skips_dislike_filter(mock_model, mock_db):
+    from algorithm.history import clear_history
+    mock_model.query.filter_by.return_value.all.return_value = [
+        _event(), _event(liked=False, disliked=True)]
+    assert clear_history("sess1", keep_dislikes=False) == 2
+    mock_model.query.filter_by.return_value.filter.assert_not_called()
+
+
+@patch("algorithm.history.WatchEvent")
+def test_overlapping_dislikes_store_each_word_once(mock_model):
+    from algorithm.history import get_disliked_signals, get_blacklisted_words
+    first = _event(video_id="vid1", title="Viral Prank", hashtags=["viral", "prank"],
+                   liked=False, disliked=True)
+    second = _event(video_id="vid2", title="Viral Challenge", hashtags=["#Viral"],
+                    liked=False, disliked=True)
+    _mock_query(mock_model, [first, second])
+    words = get_blacklisted_words("sess1")
+    assert words == ["challenge", "prank", "viral"]
+    penalties = get_disliked_signals("sess1")
+    assert penalties["viral"] > penalties["prank"]
+
+
+@patch("algorithm.history.WatchEvent")
+def test_title_punctuation_matches_hashtag(mock_model):
+    from algorithm.history import get_disliked_signals
+    ev = _event(title="Pasta, the WORST!", hashtags=["pasta"], liked=False, disliked=True)
+    _mock_query(mock_model, [ev])
+    penalties = get_disliked_signals("sess1")
+    assert set(penalties) == {"pasta", "worst"}
+
+
+@patch("algorithm.history.db")
+def test_import_inserts_out_of_order_records_oldest_first(mock_db):
+    from algorithm.history import import_history
+    newer = _event(video_id="new", timestamp=datetime(2024, 1, 3)).to_dict()
+    older = _event(video_id="old", timestamp=datetime(2024, 1, 1)).to_dict()
+    assert import_history("sess1", [newer, older]) == 2
+    added = [c.args[0].video_id for c in mock_db.session.add.call_args_list]
+    assert added == ["old", "new"]
+
+
+def test_bulk_add_matches_sequential_adds():
+    from algorithm.history import add_watches, history_version
+
+    def watches():
+        return [
+            _event(video_id="b", timestamp=datetime(2024, 1, 2)),
+            _event(video_id="a", timestamp=datetime(2024, 1, 1), liked=False, disliked=True),
+            _event(video_id="c", timestamp=datetime(2024, 1, 3)),
+        ]
+
+    def run(batches, sid):
+        with patch("algorithm.history.db") as mock_db, \
+             patch("algorithm.history.WatchEvent") as mock_model:
+            mock_model.query.filter_by.return_value.count.return_value = 3
+            before = history_version(sid)
+            totals = [add_watches(sid, batch) for batch in batches]
+            added = [(c.args[0].session_id, c.args[0].video_id, c.args[0].disliked)
+                     for c in mock_db.session.add.call_args_list]
+            return added, totals[-1], history_version(sid) - before, mock_db.session.commit
+
+    bulk_added, total, bulk_bumps, bulk_commit = run([watches()], "bulk")
+    ordered = sorted(watches(), key=lambda ev: ev.timestamp)
+    seq_added, _, seq_bumps, _ = run([[ev] for ev in ordered], "bulk")
+    assert bulk_added == seq_added
+    assert [vid for _, vid, _ in bulk_added] == ["a", "b", "c"]
+    assert total == 3
+    assert (bulk_bumps, seq_bumps) == (1, 3)
+    bulk_commit.assert_called_once()
+
+
+def test_stop_words_for_language():
+    from config import FEED_CONFIG
+    assert FEED_CONFIG.stop_words_for(None) == FEED_CONFIG.stop_words
+    assert FEED_CONFIG.stop_words_for("xx") == FEED_CONFIG.stop_words
+    italian = FEED_CONFIG.stop_words_for("it-IT")
+    assert {"di", "the"} <= italian
+
+
+@patch("algorithm.history.WatchEvent")
+def test_disliked_title_uses_video_language(mock_model):
+    from algorithm.history import get_disliked_signals
+    ev = _event(title="Scherzo con della panna", hashtags=[], language="it",
+                liked=False, disliked=True)
+    _mock_query(mock_model, [ev])
+    assert set(get_disliked_signals("sess1")) == {"scherzo", "panna"}
+
+
+@patch("algorithm.history.WatchEvent")
+def test_rewatch_counts_normalise_titles(mock_model):
+    from algorithm.history import get_rewatch_counts
+    _mock_query(mock_model, [
+        _event(title="Pasta Carbonara"), _event(title="  pasta  CARBONARA "),
+        _event(title="Space Documentary"),
+    ])
+    assert get_rewatch_counts("sess1") == {"pasta carbonara": 2, "space documentary": 1}
+
+
+def test_repeated_title_is_boosted():
+    from algorithm.history import get_weighted_history
+    from config import FEED_CONFIG
+    now = datetime.utcnow()
+    events = [
+        _event(video_id="a1", title="Pasta Carbonara", timestamp=now),
+        _event(video_id="a2", title="pasta carbonara", timestamp=now),
+        _event(video_id="b", title="Space Documentary", timestamp=now),
+    ]
+    with patch("algorithm.history.WatchEvent") as mock_model:
+        _mock_query(mock_model, events)
+        weights = {h["video_id"]: h["weight"] for h in get_weighted_history("sess1")}
+
+    boost = 1.0 + FEED_CONFIG.repeat_watch_boost
+    assert weights["a1"] == pytest.approx(weights["b"] * boost)
+    assert weights["a2"] == pytest.approx(weights["a1"])
+
+
+@patch("algorithm.history.db")
+@patch("algorithm.history.WatchEvent")
+def test_merge_sessions_moves_events_onto_one_timeline(mock_model, mock_db):
+    from algorithm.history import merge_sessions, history_version, get_weighted_history
+    from algorithm.feed import _hybrid_keywords, _build_corpus
+    phone = _event(video_id="p1", title="Pasta Carbonara", timestamp=datetime.utcnow())
+    tablet = _event(video_id="t1", title="Space Documentary", hashtags=["space"],
+                    timestamp=datetime.utcnow())
+    tablet.session_id = "tablet"
+    mock_model.query.filter_by.return_value.all.return_value = [tablet]
+    before = history_version("sess1")
+
+    assert merge_sessions("sess1", "tablet") == 1
+    assert tablet.session_id == "sess1"
+    assert history_version("sess1") == before + 1
+    assert merge_sessions("sess1", "sess1") == 0
+
+    _mock_query(mock_model, [tablet, phone])
+    keywords = _hybrid_keywords(_build_corpus(get_weighted_history("sess1")), {}, n=8)
+    assert "pasta" in keywords and "space" in keywords
+
+
+@patch("algorithm.history.WatchEvent")
+def test_hourly_distribution_counts_by_local_hour(mock_model):
+    from algorithm.history import get_hourly_distribution
+    # Recorded 22:15 local while the server clock said 03:15 UTC
+    late = _event(hour_of_day=22, timestamp=datetime(2024, 1, 2, 3, 15))
+    unknown = _event()
+    unknown.hour_of_day = None
+    mock_model.query.filter_by.return_value.all.return_value = [
+        late, _event(hour_of_day=22, timestamp=datetime(2024, 1, 2, 22, 50)),
+        _event(hour_of_day=7, timestamp=datetime(2024, 1, 2, 7, 0)), unknown,
+    ]
+    hours = get_hourly_distribution("sess1")
+    assert len(hours) == 24
+    assert hours[22] == 2 and hours[7] == 1
+    assert sum(hours) == 3
+
+
+def test_completed_uses_threshold_and_ignores_zero_length():
+    assert _event(watch_time_seconds=230.0).completed() is True     # 96 %
+    assert _event(watch_time_seconds=200.0).completed() is False    # 83 %
+    assert _event(watch_time_seconds=200.0).completed(0.8) is True
+    empty = WatchEvent(video_duration_seconds=0.0, watch_time_seconds=30.0)
+    assert empty.completed() is False and empty.completed(0.0) is False
+
+
+@patch("algorithm.history.WatchEvent")
+def test_completion_rate_over_watches(mock_model):
+    from algorithm.history import completion_rate
+    _mock_query(mock_model, [
+        _event(watch_time_seconds=240.0), _event(watch_time_seconds=60.0),
+        _event(watch_time_seconds=230.0), _event(watch_time_seconds=120.0),
+    ])
+    assert completion_rate("sess1") == pytest.approx(0.5)
+    _mock_query(mock_model, [])
+    assert completion_rate("sess1") == 0.0
+
+
+@patch("algorithm.history.get_disliked_signals", return_value={"viral": 2.0})
+@patch("algorithm.history.WatchEvent")
+def test_top_hashtags_counts_likes_double(mock_model, mock_signals):
+    from algorithm.history import get_top_hashtags
+    _mock_query(mock_model, [
+        _event(hashtags=["pasta", "italian"], liked=True),
+        _event(hashtags=["pasta", "viral"], liked=False),
+        _event(hashtags=["space"], liked=False),
+    ])
+    assert get_top_hashtags("sess1", n=3) == [("pasta", 3), ("italian", 2), ("space", 1)]
+
+
+def _rule(tag, blocked):
+    from database.models import TagRule
+    return TagRule(session_id="sess1", tag=tag, blocked=blocked)
+
+
+@patch("algorithm.history.WatchEvent")
+def test_tag_rules_override_video_dislikes(mock_model):
+    from algorithm.history import get_disliked_signals
+    ev = _event(title="Viral Prank", hashtags=["viral", "prank"],
+                liked=False, disliked=True)
+    _mock_query(mock_model, [ev, ev])
+    rules = [_rule("prank", False), _rule("slime", True)]
+    with patch("algorithm.history.TagRule") as mock_rule:
+        mock_rule.query.filter_by.return_value.all.return_value = rules
+        penalties = get_disliked_signals("sess1")
+    assert "prank" not in penalties
+    assert penalties["slime"] == max(penalties.values()) >= 2.0
+
+
+@patch("algorithm.history.db")
+def test_block_tag_upserts_one_rule(mock_db):
+    from algorithm.history import block_tag, allow_tag, history_version
+    with patch("algorithm.history.TagRule") as mock_rule:
+        mock_rule.query.filter_by.return_value.first.return_value = None
+        before = history_version("sess1")
+        assert block_tag("sess1", " #Slime ") is True
+        mock_rule.assert_called_once_with(session_id="sess1", tag="slime", blocked=True)
+        assert history_version("sess1") > before
+
+        existing = _rule("slime", True)
+        mock_rule.query.filter_by.return_value.first.return_value = existing
+        assert allow_tag("sess1", "slime") is True
+        assert existing.blocked is False
+    assert mock_db.session.add.call_count == 1
+    assert block_tag("sess1", "#") is False
+
+
+@patch("algorithm.history.db")
+def test_csv_import_skips_malformed_rows(mock_db, tmp_path):
+    from algorithm.history import load_history_csv
+    path = tmp_path / "watches.csv"
+    path.write_text(
+        "watch_time,video_length,video_name,hashtags,liked,disliked,watched_at\n"
+        '120,240,"Pasta, Quick",pasta|italian,true,false,1704067200\n'
+        "30,60,Prank Fail,viral,no,yes,2024-01-02T08:00:00+01:00\n"
+        "oops,60,Bad Number,,0,0,1704067200\n"
+        "30,60,Both Flags,,1,1,1704067200\n"
+        "30,60,Too Short\n"
+    )
+    assert load_history_csv("sess1", str(path)) == (2, 3)
+    added = [c.args[0] for c in mock_db.session.add.call_args_list]
+    assert [ev.video_title for ev in added] == ["Pasta, Quick", "Prank Fail"]
+    assert added[0].hashtag_list == ["pasta", "italian"]
+    assert added[0].timestamp == datetime(2024, 1, 1)
+    assert added[1].disliked is True
+    assert added[1].hour_of_day == 7
+
+
+@patch("algorithm.history.db")
+@patch("algorithm.history.WatchEvent")
+def test_removing_a_disliked_watch_keeps_shared_words(mock_model, mock_db):
+    from algorithm.history import remove_watch, get_disliked_signals, history_version
+    gone = _event(video_id="vid1", title="Viral Prank", hashtags=["viral", "prank"],
+                  liked=False, disliked=True)
+    kept = _event(video_id="vid2", title="Viral Dance", hashtags=["viral"],
+                  liked=False, disliked=True)
+    mock_model.query.filter_by.return_value.first.return_value = gone
+    before = history_version("sess1")
+    assert remove_watch("sess1", gone.timestamp) is True
+    mock_db.session.delete.assert_called_once_with(gone)
+    assert history_version("sess1") > before
+
+    _mock_query(mock_model, [kept])
+    penalties = get_disliked_signals("sess1")
+    assert "viral" in penalties
+    assert "prank" not in penalties
+
+    mock_model.query.filter_by.return_value.first.return_value = None
+    assert remove_watch("sess1", datetime(2020, 1, 1)) is False
+
+
+@patch("algorithm.history.db")
+@patch("algorithm.history.WatchEvent")
+def test_history_cap_evicts_oldest(mock_model, mock_db):
+    from datetime import timedelta
+    from algorithm.history import get_disliked_signals, get_weighted_history, trim_history
+    now = datetime.utcnow()
+    newest = _event(video_id="new", title="Guitar Basics", timestamp=now)
+    middle = _event(video_id="mid", title="Drum Fills", timestamp=now - timedelta(days=2))
+    oldest = _event(video_id="old", title="Pasta", timestamp=now - timedelta(days=5))
+    disliked = _event(video_id="bad", title="Viral Prank", hashtags=["prank"], liked=False,
+                      disliked=True, timestamp=now - timedelta(days=9))
+
+    # Only the non-disliked query is answered: a trim that skipped the
+    # filter would not see these rows (and would evict the dislike)
+    newest_first = mock_model.query.filter_by.return_value.filter.return_value.order_by.return_value
+    newest_first.offset.side_effect = lambda k: MagicMock(
+        all=MagicMock(return_value=[newest, middle, oldest][k:]))
+    assert trim_history("sess1") == 0                     # Unbounded by default
+    assert trim_history("sess1", max_events=2) == 1
+    mock_db.session.delete.assert_called_once_with(oldest)
+
+    _mock_query(mock_model, [disliked])
+    assert "prank" in get_disliked_signals("sess1")       # Still blocked
+
+    _mock_query(mock_model, [newest, middle])
+    weights = {h["video_id"]: h["weight"] for h in get_weighted_history("sess1")}
+    assert set(weights) == {"new", "mid"}
+    assert weights["new"] > weights["mid"]
+
+
+def test_recency_multiplier_favours_recent_terms():
+    from datetime import timedelta
+    from algorithm.feed import _build_corpus, _hybrid_keywords
+    from algorithm.history import get_weighted_history
+    from config import FEED_CONFIG
+    now = datetime.utcnow()
+    old = now - timedelta(days=FEED_CONFIG.watch_half_life_days / 2)
+    events = [
+        _event(video_id="g", title="Guitar", hashtags=[], liked=False, timestamp=now),
+        _event(video_id="p1", title="Pasta", hashtags=[], liked=False, timestamp=old),
+        _event(video_id="p2", title="Pasta", hashtags=[], liked=False, timestamp=old),
+    ]
+
+    def top_term():
+        with patch("algorithm.history.WatchEvent") as mock_model:
+            _mock_query(mock_model, events)
+            history = get_weighted_history("sess1")
+        return _hybrid_keywords(_build_corpus(history), {}, n=1, markov_fraction=0.0)
+
+    with patch.object(FEED_CONFIG, "repeat_watch_boost", 0.0):
+        assert top_term() == ["pasta"]                 # Two old watches beat one new
+        with patch.object(FEED_CONFIG, "recency_multiplier", 3.0):
+            assert top_term() == ["guitar"]
+
+
+@patch("algorithm.history.WatchEvent")
+def test_active_watches_skip_misclicks(mock_model):
+    from algorithm.history import get_active_watches
+    from config import GUARDRAIL_CONFIG
+    floor = GUARDRAIL_CONFIG.min_watch_seconds
+    events = [
+        _event(video_id="ok", watch_time_seconds=120.0),
+        _event(video_id="edge", watch_time_seconds=float(floor)),
+        _event(video_id="blip", watch_time_seconds=floor - 1.0),
+    ]
+    _mock_query(mock_model, events)    # Dislikes filtered by the query
+    assert [ev.video_id for ev in get_active_watches("sess1")] == ["ok", "edge"]
+
+
+@patch("algorithm.history.db")
+@patch("algorithm.history.WatchEvent")
+def test_expired_dislikes_stop_penalising(mock_model, mock_db):
+    from algorithm.history import expire_dislikes, get_disliked_signals
+    from database.models import ParentSettings
+    old = _event(video_id="old", title="Viral Prank", hashtags=["viral", "prank"],
+                 liked=False, disliked=True, timestamp=datetime(2024, 1, 1))
+    recent = _event(video_id="new", title="Viral Dance", hashtags=["viral"],
+                    liked=False, disliked=True, timestamp=datetime(2024, 6, 1))
+    _mock_query(mock_model, [old, recent])
+    settings = ParentSettings(session_id="sess1")
+    with patch("algorithm.history.ParentSettings") as mock_settings:
+        mock_settings.query.filter_by.return_value.first.return_value = settings
+        assert "prank" in get_disliked_signals("sess1")
+
+        expire_dislikes("sess1", datetime(2024, 3, 1))
+        assert settings.dislikes_forgiven_before == datetime(2024, 3, 1)
+        penalties = get_disliked_signals("sess1")
+    assert "prank" not in penalties
+    assert "viral" in penalties          # Still backed by the recent dislike
+    assert "dance" in penalties
+
+
+@patch("algorithm.history.WatchEvent")
+def test_later_watches_win_a_disliked_word_back(mock_model):
+    from config import FEED_CONFIG, DislikeMode
+    from algorithm.feed import _apply_dislikes
+    from algorithm.history import get_disliked_signals, term_penalty
+    disliked = _event(video_id="bad", title="Viral Prank", hashtags=["viral"],
+                      liked=False, disliked=True, timestamp=datetime(2024, 1, 1))
+    later = [
+        _event(video_id=f"ok{i}", title="Viral Dance", hashtags=["dance"],
+               timestamp=datetime(2024, 1, 2 + i))
+        for i in range(3)
+    ]
+    _mock_query(mock_model, [disliked], ordered=later[:2])
+
+    full = term_penalty("sess1", "#viral")
+    assert full > 0
+    with patch.object(FEED_CONFIG, "dislike_recovery_watches", 3):
+        assert term_penalty("sess1", "viral") == pytest.approx(full / 3)
+        assert term_penalty("sess1", "prank") > 0          # Never watched again
+        _mock_query(mock_model, [disliked], ordered=later)
+        assert term_penalty("sess1", "viral") == 0.0
+        scores = _apply_dislikes({"viral": 0.4, "prank": 0.4},
+                                 get_disliked_signals("sess1"), DislikeMode.PENALIZE)
+    assert scores == {"viral": 0.4}
+
+
+def test_length_weighting_favours_long_videos():
+    from config import FEED_CONFIG
+    from algorithm.feed import _build_corpus
+    from algorithm.history import get_weighted_history, _length_factor
+    from algorithm.tfidf import TFIDF
+    now = datetime.utcnow()
+    short = WatchEvent.build("sess1", "short", 30.0, 30.0, title="Volcano Short",
+                             hashtags=["lava"], timestamp=now)
+    long = WatchEvent.build("sess1", "long", 1800.0, 1800.0, title="Glacier Documentary",
+                            hashtags=["ice"], timestamp=now)
+
+    def history():
+        with patch("algorithm.history.WatchEvent") as mock_model:
+            _mock_query(mock_model, [short, long])
+            return get_weighted_history("sess1")
+
+    def term_scores():
+        corpus = _build_corpus(history())
+        tfidf = TFIDF()
+        tfidf.fit(corpus)
+        return dict(tfidf.top_words(corpus, top_n=10))
+
+    plain = {h["video_id"]: h["weight"] for h in history()}
+    assert plain["short"] == pytest.approx(plain["long"])
+    scores = term_scores()
+    assert scores["glacier"] == pytest.approx(scores["volcano"])
+    with patch.object(FEED_CONFIG, "length_weighting", True):
+        weighted = {h["video_id"]: h["weight"] for h in history()}
+        assert weighted["long"] / weighted["short"] == pytest.approx(
+            _length_factor(1800.0) / _length_factor(30.0))
+        scores = term_scores()
+        assert min(scores["glacier"], scores["ice"]) > max(scores["volcano"], scores["lava"])
+    assert _length_factor(0.0) == _length_factor(-5.0) == 1.0
+
+
+def test_abandoned_watches_contribute_no_terms():
+    from config import FEED_CONFIG
+    from algorithm.feed import _build_corpus
+    from algorithm.history import get_weighted_history
+    from algorithm.tfidf import TFIDF
+    now = datetime.utcnow()
+    kept = _event(video_id="kept", title="Volcano Eruption", hashtags=["lava"],
+                  watch_time_seconds=192.0, liked=False, timestamp=now)    # 80 %
+    bailed = _event(video_id="bailed", title="Drum Fills", hashtags=["drums"],
+                    watch_time_seconds=12.0, liked=False, timestamp=now)  # 5 %
+
+    def terms():
+        with patch("algorithm.history.WatchEvent") as mock_model:
+            _mock_query(mock_model, [kept, bailed])
+            history = get_weighted_history("sess1")
+        assert {h["video_id"] for h in history} == {"kept", "bailed"}
+        corpus = _build_corpus(history)
+        tfidf = TFIDF()
+        tfidf.fit(corpus)
+        return dict(tfidf.top_words(corpus, top_n=None))
+
+    assert {"drum", "fills", "volcano"} <= set(terms())
+    with patch.object(FEED_CONFIG, "abandon_ratio", 0.1):
+        assert set(terms()) == {"volcano", "eruption", "lava"}
+        bailed.liked = True                 # A like outweighs bailing early
+        assert "drum" in terms()
+
+
+def test_weights_follow_engagement_with_a_floor():
+    from config import FEED_CONFIG
+    from algorithm.history import get_weighted_history
+    now = datetime.utcnow()
+    full = _event(video_id="full", watch_time_seconds=240.0, liked=False, timestamp=now)
+    skim = _event(video_id="skim", title="Drum Fills", watch_time_seconds=6.0,
+                  liked=False, timestamp=now)
+    unwatched = _event(video_id="unwatched", title="Lava Lamps", watch_time_seconds=0.0,
+                       liked=False, timestamp=now)
+
+    def weights():
+        with patch("algorithm.history.WatchEvent") as mock_model:
+            _mock_query(mock_model, [full, skim, unwatched])
+            return {h["video_id"]: h["weight"] for h in get_weighted_history("sess1")}
+
+    weighted = weights()
+    assert weighted["full"] > weighted["skim"] > 0       # Floor keeps the skim
+    assert weighted["skim"] == pytest.approx(FEED_CONFIG.engagement_floor)
+    assert weighted["unwatched"] == pytest.approx(FEED_CONFIG.engagement_floor)
+    with patch.object(FEED_CONFIG, "attention_weighted", True):
+        assert weights() == pytest.approx(weighted)      # Keyword-only, see test_feed
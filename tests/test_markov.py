@@ -1,6 +1,7 @@
 """Tests for the Markov chain."""
 
 import pytest
+from unittest.mock import patch
 from algorithm.markov import MarkovChain
 
 
@@ -46,3 +47,165 @@ def test_short_document_handled_gracefully():
     mc.train([("one", 1.0)])  # Too short for bigram
     result = mc.generate(length=5)
     assert isinstance(result, list)
+
+
+def test_seeded_chains_generate_identical_walks():
+    corpus = [
+        ("python tutorial for beginners", 1.0),
+        ("python coding tips and tricks", 1.0),
+        ("coding tutorial advanced python", 0.5),
+    ]
+    a = MarkovChain.with_seed(42, order=1)
+    b = MarkovChain.with_seed(42, order=1)
+    a.train(corpus)
+    b.train(corpus)
+    assert a.generate(length=8) == b.generate(length=8)
+
+
+def test_single_candidate_transition_does_not_fail():
+    mc = MarkovChain.with_seed(7, order=1)
+    mc.train([("alpha beta", 1.0)])
+    result = mc.generate(length=2)
+    assert result == ["alpha", "beta"]
+
+
+def test_frequent_transitions_are_sampled_more_often():
+    mc = MarkovChain.with_seed(3, order=1)
+    mc.train([("italian pasta", 1.0)] * 10 + [("italian pizza", 1.0)])
+    counts = {"pasta": 0, "pizza": 0}
+    for _ in range(200):
+        counts[mc.generate(length=2)[1]] += 1
+    assert counts["pasta"] > counts["pizza"] * 3
+
+
+def test_higher_order_backs_off_to_shorter_context():
+    """A lone start word has no full bigram context, so the walk must back off."""
+    mc = MarkovChain.with_seed(1, order=2)
+    mc.train([("red apple pie", 1.0)])
+    assert mc.generate(length=3) == ["red", "apple", "pie"]
+
+
+def test_order_one_has_no_backoff_tables():
+    mc = MarkovChain(order=1)
+    mc.train([("red apple pie", 1.0)])
+    assert mc._backoff == {}
+    assert mc.top_transitions() == [("apple", 1.0), ("pie", 1.0)]
+
+
+def test_stop_words_never_become_transitions():
+    mc = MarkovChain(order=1)
+    mc.train([("how to make pasta carbonara", 1.0)])
+    words = {w for w, _ in mc.top_transitions()}
+    assert words == {"pasta", "carbonara"}
+
+
+def test_title_of_only_stop_words_contributes_nothing():
+    mc = MarkovChain(order=1)
+    mc.train([("how to do it", 1.0)])
+    assert mc.generate() == []
+
+
+@pytest.mark.parametrize("order", [1, 2])
+def test_next_words_are_distinct_and_ranked(order):
+    mc = MarkovChain(order=order)
+    mc.train([
+        ("pasta carbonara recipe", 2.0),
+        ("pasta salad ideas", 1.0),
+        ("pasta carbonara classic", 1.0),
+    ])
+    assert mc.next_words("Pasta") == ["carbonara", "salad"]
+    assert mc.next_words("unknown") == []
+
+
+def test_restart_seeds_continue_past_dead_ends():
+    mc = MarkovChain.with_seed(7, order=1)
+    mc.train([("pasta carbonara", 1.0), ("space rocket", 1.0), ("guitar lesson", 1.0)])
+    walk = mc.generate(6, restart_seeds=["pasta", "space", "guitar"])
+    assert sorted(walk) == ["carbonara", "guitar", "lesson", "pasta", "rocket", "space"]
+
+
+def test_restart_seeds_stop_when_exhausted():
+    mc = MarkovChain.with_seed(7, order=1)
+    mc.train([("pasta carbonara", 1.0), ("space rocket", 1.0)])
+    walk = mc.generate(10, restart_seeds=["space"])
+    assert walk == ["space", "rocket"]
+
+
+def test_seed_scores_weight_the_first_restart_seed():
+    mc = MarkovChain.with_seed(4, order=1)
+    mc.train([("pasta carbonara", 1.0), ("space rocket", 1.0)])
+    starts = {"pasta": 0, "space": 0}
+    for _ in range(200):
+        starts[mc.generate(4, restart_seeds=["pasta", "space"],
+                           seed_scores={"space": 9.0, "pasta": 1.0})[0]] += 1
+    assert starts["space"] > starts["pasta"] * 3
+
+
+def test_transitions_use_a_sliding_window_of_order_words():
+    mc = MarkovChain(order=2)
+    mc.train([("red apple pie crust", 1.0)])
+    assert {ctx: dict(nexts) for ctx, nexts in mc._transitions.items()} == {
+        ("red", "apple"): {"pie": 1.0},
+        ("apple", "pie"): {"crust": 1.0},
+    }
+    assert dict(mc._backoff[1][("pie",)]) == {"crust": 1.0}
+
+
+def test_dead_end_falls_back_to_a_known_word():
+    """"pie" is never followed by anything, yet the walk still reaches full length."""
+    mc = MarkovChain.with_seed(3, order=1)
+    mc.train([("apple pie", 1.0)])
+    walk = mc.generate(length=5)
+    assert len(walk) == 5
+    assert set(walk) <= {"apple", "pie"}
+
+
+def test_hashtag_weight_scales_transitions_into_tags():
+    mc = MarkovChain(order=1, hashtag_weight=3.0)
+    mc.train([("best goals | football", 1.0)])
+    assert dict(mc.top_transitions()) == {"goals": 1.0, "football": 3.0}
+
+
+def test_seed_scores_weight_the_start_word():
+    docs = [("pasta carbonara", 1.0), ("guitar lesson", 1.0)]
+    starts = {"pasta": 0, "guitar": 0}
+    mc = MarkovChain.with_seed(11, order=1)
+    mc.train(docs)
+    for _ in range(300):
+        # "rocket" is unknown to the chain, so it is never chosen (KeyError otherwise)
+        starts[mc.generate(2, seed_scores={"pasta": 3.0, "guitar": 1.0, "rocket": 9.0})[0]] += 1
+    assert starts["pasta"] > starts["guitar"] * 2
+
+    first = MarkovChain.with_seed(5, order=1)
+    second = MarkovChain.with_seed(5, order=1)
+    first.train(docs)
+    second.train(docs)
+    scores = {"pasta": 1.0, "guitar": 1.0}
+    assert [first.generate(2, seed_scores=scores) for _ in range(5)] == \
+           [second.generate(2, seed_scores=scores) for _ in range(5)]
+
+
+def test_unusable_seed_scores_fall_back_to_title_openings():
+    mc = MarkovChain.with_seed(2, order=1)
+    mc.train([("pasta carbonara", 1.0)])
+    assert mc.generate(2, seed_scores={"rocket": 1.0, "carbonara": 0.0}) == ["pasta", "carbonara"]
+
+
+def test_avoid_chain_skips_disliked_followers():
+    mc = MarkovChain.with_seed(5, order=1)
+    mc.train([("clickbait garbage", 1.0), ("clickbait explained", 1.0)])
+    mc.avoid = MarkovChain(order=1)
+    mc.avoid.train([("clickbait garbage compilation", 1.0)])
+
+    assert mc.next_words("clickbait") == ["explained"]
+    for _ in range(20):
+        assert mc.generate(2, seed_scores={"clickbait": 1.0}) == ["clickbait", "explained"]
+
+
+def test_restart_walk_leaves_a_cycle_promptly():
+    mc = MarkovChain.with_seed(1, order=1)
+    mc.train([("ping pong ping pong ping", 1.0)])
+    with patch.object(mc, "_next_word", wraps=mc._next_word) as step:
+        walk = mc.generate(5, restart_seeds=["ping"])
+    assert sorted(walk) == ["ping", "pong"]
+    assert step.call_count <= 3
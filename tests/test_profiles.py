@@ -0,0 +1,54 @@
+"""Tests for per-user profiles on a shared device."""
+
+import pytest
+from unittest.mock import patch
+
+
+@pytest.fixture(autouse=True)
+def _fresh_keyword_cache():
+    from algorithm.feed import clear_keyword_cache
+    clear_keyword_cache()
+    yield
+    clear_keyword_cache()
+
+
+def _entry(video_id, title, hashtags):
+    return {"video_id": video_id, "title": title, "hashtags": hashtags,
+            "liked": True, "weight": 1.0, "completion": 1.0}
+
+
+HISTORIES = {
+    "tv:parent": [
+        _entry("p1", "Stock Market Basics", ["investing", "finance"]),
+        _entry("p2", "Index Fund Investing", ["investing", "finance"]),
+    ],
+    "tv:kid": [
+        _entry("k1", "Dinosaur Songs", ["dinosaurs", "songs"]),
+        _entry("k2", "Dinosaur Facts", ["dinosaurs", "science"]),
+    ],
+}
+
+
+@patch("algorithm.feed.has_disliked_watches", return_value=False)
+@patch("algorithm.feed.get_disliked_signals", return_value={})
+@patch("algorithm.feed.get_weighted_history", side_effect=lambda sid: HISTORIES.get(sid, []))
+def test_profiles_query_their_own_history(mock_hist, mock_dis, mock_has):
+    from algorithm.profiles import Profiles
+    profiles = Profiles("tv")
+    parent, kid = profiles.generate_query("parent", 4), profiles.generate_query("kid", 4)
+    assert "investing" in parent and "dinosaurs" in kid
+    assert not set(parent) & set(kid)
+    assert profiles.generate_query("guest", 4) == []        # Fresh, empty profile
+    assert profiles.list() == ["parent", "kid", "guest"]
+
+
+@patch("algorithm.profiles.record_watch", return_value={"recorded": True})
+def test_add_watch_and_switch_use_the_profile_session(mock_record):
+    from algorithm.profiles import Profiles
+    profiles = Profiles("tv")
+    assert profiles.switch("kid") == "tv:kid" and profiles.active == "kid"
+    profiles.add_watch("kid", video_id="k1", video_title="Dinosaur Songs",
+                       video_hashtags="dinosaurs", watch_time_seconds=60,
+                       video_duration_seconds=90)
+    assert mock_record.call_args.args == ("tv:kid",)
+    assert mock_record.call_args.kwargs["video_id"] == "k1"
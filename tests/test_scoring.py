@@ -0,0 +1,60 @@
+"""Tests for the pluggable term scorers."""
+
+from unittest.mock import patch
+
+import pytest
+from algorithm.scoring import Bm25Scorer, TfIdfScorer
+from algorithm.tfidf import TFIDF
+
+DOCS = [
+    ("pasta carbonara recipe | cooking pasta", 2.0),
+    ("viral pasta prank | prank", 1.0),
+    ("bread baking basics | baking", 1.0),
+]
+
+
+def test_tfidf_scorer_matches_top_words():
+    tfidf = TFIDF()
+    tfidf.fit(DOCS)
+    assert TfIdfScorer().score_terms(DOCS) == tfidf.top_words(DOCS, top_n=None)
+
+
+def test_blacklist_drops_words_and_phrases():
+    scorer = TfIdfScorer(include_bigrams=True)
+    terms = [t for t, _ in scorer.score_terms(DOCS, blacklist={"prank"})]
+    assert "pasta" in terms and "pasta carbonara" in terms
+    assert not any("prank" in t.split() for t in terms)
+
+
+def test_unset_options_follow_config():
+    from config import FEED_CONFIG
+    with patch.object(FEED_CONFIG, "tfidf_include_bigrams", True):
+        terms = [t for t, _ in TfIdfScorer().score_terms(DOCS)]
+    assert "pasta carbonara" in terms
+    assert "pasta carbonara" not in [t for t, _ in TfIdfScorer().score_terms(DOCS)]
+
+
+LESSONS = [
+    ("Chess Openings | chess", 1.0),
+    ("Guitar Lesson Guitar Chords Guitar Scales For Total Beginners Practice Routine"
+     " | guitar music", 1.0),
+    ("Chess Endgame Lesson | chess", 1.0),
+]
+
+
+def test_bm25_favours_words_from_short_titles():
+    def rank(scorer):
+        return [t for t, _ in scorer.score_terms(LESSONS)]
+
+    tfidf, bm25 = rank(TfIdfScorer()), rank(Bm25Scorer())
+    # "lesson" is in two documents, but one is a long, guitar-heavy title
+    assert tfidf.index("lesson") < tfidf.index("openings")
+    assert bm25.index("openings") < bm25.index("lesson")
+    assert tfidf[0] == bm25[0] == "chess"
+
+
+def test_bm25_blacklist_and_parameters():
+    terms = [t for t, _ in Bm25Scorer().score_terms(LESSONS, blacklist={"guitar"})]
+    assert "guitar" not in terms and "chess" in terms
+    with pytest.raises(ValueError):
+        Bm25Scorer(b=1.5)
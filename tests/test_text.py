@@ -0,0 +1,67 @@
+"""Tests for the shared text helpers."""
+
+import pytest
+from algorithm.text import edit_distance, normalise_tag, set_tokenizer, stem, tokenize
+
+
+@pytest.mark.parametrize("word, expected", [
+    ("recipes", "recipe"),
+    ("recipe", "recipe"),
+    ("stories", "story"),
+    ("dishes", "dish"),
+    ("cooking", "cook"),
+    ("cooked", "cook"),
+    ("baking", "bake"),
+    ("running", "run"),
+    ("opening", "open"),
+    ("glass", "glass"),
+    ("grandma's", "grandma"),
+    ("art", "art"),
+])
+def test_stem(word, expected):
+    assert stem(word) == expected
+
+
+def test_tokenize_strips_punctuation_and_case():
+    assert tokenize("Pasta, CARBONARA!") == ["pasta", "carbonara"]
+    assert tokenize("#viral @chef (pasta)") == ["viral", "chef", "pasta"]
+
+
+def test_tokenize_keeps_apostrophes_and_hyphens():
+    assert tokenize("Grandma\u2019s Step-by-Step Pasta") == [
+        "grandma's", "step-by-step", "pasta"]
+    assert tokenize("rock 'n' roll -- live") == ["rock", "n", "roll", "live"]
+
+
+def test_tokenize_filters_stop_words_and_short_tokens():
+    assert tokenize("how to cook a pie | oven", frozenset({"how", "to"}), 3) == [
+        "cook", "pie", "oven"]
+
+
+def test_custom_tokenizer_is_used_everywhere():
+    from algorithm.tfidf import TFIDF
+
+    def no_emoji(text):
+        kept = "".join(ch for ch in text.lower() if ch.isalnum() or ch.isspace())
+        return kept.replace("\u00df", "ss").split()
+
+    assert normalise_tag("#Pasta ") == "pasta"
+    set_tokenizer(no_emoji)
+    try:
+        assert tokenize("Stra\u00dfe \U0001F355Pizza!", min_length=3) == ["strasse", "pizza"]
+        assert normalise_tag("#Gro\u00dfe") == "grosse"
+        tfidf = TFIDF(stop_words=[])
+        docs = [("\U0001F355 Pizza | pizza", 1.0)]
+        tfidf.fit(docs)
+        assert [w for w, _ in tfidf.top_words(docs)] == ["pizza"]
+    finally:
+        set_tokenizer(None)
+    assert tokenize("Stra\u00dfe") == ["stra\u00dfe"]
+
+
+def test_edit_distance():
+    assert edit_distance("carbonara", "carbonaraa") == 1
+    assert edit_distance("kitten", "sitting") == 3
+    assert edit_distance("", "abc") == 3
+    assert edit_distance("kitten", "sitting", limit=1) == 2      # Gave up early
+
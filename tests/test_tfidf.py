@@ -1,5 +1,7 @@
 """Tests for the TF-IDF model."""
 
+import math
+
 import pytest
 from algorithm.tfidf import TFIDF
 
@@ -45,3 +47,155 @@ def test_empty_docs_returns_empty():
     tfidf.fit([])
     result = tfidf.top_words([], top_n=5)
     assert result == []
+
+
+def test_custom_stop_words_override_default():
+    tfidf = TFIDF(stop_words={"pasta"})
+    docs = [("the pasta carbonara", 1.0)]
+    tfidf.fit(docs)
+    top_words = {w for w, _ in tfidf.top_words(docs, top_n=10)}
+    assert top_words == {"the", "carbonara"}
+
+
+def test_bigrams_are_scored_as_phrases():
+    tfidf = TFIDF(include_bigrams=True)
+    docs = [("pasta carbonara recipe | italian", 1.0)]
+    tfidf.fit(docs)
+    terms = {w for w, _ in tfidf.top_words(docs, top_n=20)}
+    assert "pasta carbonara" in terms
+    assert "carbonara recipe" in terms
+    assert "recipe italian" not in terms     # never across the | separator
+
+
+def test_bigrams_off_by_default():
+    tfidf = TFIDF()
+    docs = [("pasta carbonara recipe", 1.0)]
+    tfidf.fit(docs)
+    assert all(" " not in w for w, _ in tfidf.top_words(docs, top_n=20))
+
+
+def test_ties_are_broken_alphabetically():
+    tfidf = TFIDF()
+    docs = [("zebra mango apple", 1.0)]
+    tfidf.fit(docs)
+    result = tfidf.top_words(docs, top_n=3)
+    assert [w for w, _ in result] == ["apple", "mango", "zebra"]
+    assert result[0][1] == result[2][1]
+
+
+def test_cosine_similarity_bounds():
+    from algorithm.tfidf import cosine_similarity
+    a = {"pasta": 1.0, "italian": 0.5}
+    assert cosine_similarity(a, a) == pytest.approx(1.0)
+    assert cosine_similarity(a, {"space": 1.0}) == 0.0
+    assert cosine_similarity(a, {}) == 0.0
+
+
+def test_stemming_merges_plural_forms():
+    tfidf = TFIDF(stemming=True)
+    docs = [("easy recipes", 1.0), ("quick recipe", 1.0), ("recipes galore", 1.0)]
+    tfidf.fit(docs)
+    result = dict(tfidf.top_words(docs, top_n=10))
+    assert "recipes" in result         # most common surface form is reported
+    assert "recipe" not in result
+    assert "recip" not in result
+
+
+def test_non_finite_weights_do_not_break_ranking():
+    tfidf = TFIDF()
+    docs = [("pasta carbonara", 1.0), ("broken upload", float("nan")),
+            ("endless video", float("inf")), ("pasta salad", 0.5)]
+    tfidf.fit(docs)
+    result = tfidf.top_words(docs, top_n=10)
+    words = [w for w, _ in result]
+    assert words[0] == "pasta"
+    assert "broken" not in words and "endless" not in words
+    assert all(math.isfinite(score) for _, score in result)
+
+
+def test_top_words_are_ordered_by_score():
+    tfidf = TFIDF()
+    docs = [
+        ("guitar guitar chords", 2.0),
+        ("guitar lesson", 1.0),
+        ("drum lesson", 0.5),
+    ]
+    tfidf.fit(docs)
+    result = tfidf.top_words(docs, top_n=10)
+    scores = [s for _, s in result]
+    assert scores == sorted(scores, reverse=True)
+    assert result[0][0] == "guitar"
+
+
+def test_boosted_hashtag_outranks_title_word():
+    docs = [("ranked best moments | football", 1.0)]
+    plain = TFIDF()
+    plain.fit(docs)
+    assert plain.top_words(docs, top_n=1)[0][0] == "best"     # alphabetical tie
+    boosted = TFIDF(hashtag_weight=2.0)
+    boosted.fit(docs)
+    assert boosted.top_words(docs, top_n=1)[0][0] == "football"
+
+
+def test_smoothed_idf_keeps_words_found_everywhere():
+    docs = [("pasta carbonara", 1.0), ("pasta pesto", 1.0)]
+    smoothed = TFIDF()
+    smoothed.fit(docs)
+    assert dict(smoothed.top_words(docs, top_n=5))["pasta"] > 0
+
+    classic = TFIDF(smooth_idf=False)
+    classic.fit(docs)
+    scores = dict(classic.top_words(docs, top_n=5))
+    assert scores["pasta"] == 0.0
+    assert scores["carbonara"] == pytest.approx(math.log(2))
+
+
+def test_document_frequency_matches_a_per_word_scan():
+    """The single-pass DF must agree with scanning every document per word."""
+    docs = [
+        ("pasta pasta carbonara | pasta", 2.0),
+        ("pasta pesto | italian", 1.0),
+        ("guitar chords | music", 0.5),
+        ("pesto guitar", float("nan")),
+    ]
+    tfidf = TFIDF()
+    tfidf.fit(docs)
+
+    finite = [(text, w) for text, w in docs if math.isfinite(w)]
+    total = sum(w for _, w in finite)
+    vocab = {t for text, _ in finite for t in tfidf._terms(text)}
+    for word in vocab:
+        freq = sum(w for text, w in finite if word in tfidf._terms(text))
+        assert tfidf._idf[word] == pytest.approx(math.log((total + 1) / (freq + 1)) + 1)
+
+
+def test_empty_documents_are_skipped():
+    docs = [("pasta carbonara", 1.0), ("pasta pesto", 1.0)]
+    baseline = TFIDF()
+    baseline.fit(docs)
+    with_empty = TFIDF()
+    padded = docs + [(" | ", 1.0), ("", 2.0)]
+    with_empty.fit(padded)
+    scores = with_empty.top_words(padded, top_n=10)
+    assert all(math.isfinite(s) for _, s in scores)
+    assert scores == baseline.top_words(docs, top_n=10)
+    assert with_empty.score_document("") == {}
+
+
+def test_parallel_top_words_match_serial():
+    topics = ["pasta carbonara", "guitar chords", "space rockets", "lego cities",
+              "dinosaur facts", "recipes baking", "drawing cartoons"]
+    docs = [
+        (f"{topics[i % 7]} {topics[(i * 3) % 7].split()[1]} part | {topics[i % 5].split()[0]}",
+         1.0 / (1 + i % 11))
+        for i in range(300)
+    ]
+
+    def ranked(workers):
+        tfidf = TFIDF(stemming=True, include_bigrams=True, workers=workers)
+        tfidf.fit(docs)
+        return tfidf.top_words(docs, top_n=None)
+
+    serial, parallel = ranked(1), ranked(4)
+    assert [w for w, _ in parallel] == [w for w, _ in serial]
+    assert [s for _, s in parallel] == pytest.approx([s for _, s in serial], rel=1e-9)
@@ -32,6 +32,7 @@ def build_embed(video_id: str) -> Optional[Dict[str, Any]]:
         "description": str,
         "view_count": int,
         "like_count": int,
+        "language": str,          # ISO 639-1, "" if unknown
     }
     """
     info = get_video_info(video_id)
@@ -50,4 +51,5 @@ def build_embed(video_id: str) -> Optional[Dict[str, Any]]:
         "description": info["description"],
         "view_count": info["view_count"],
         "like_count": info["like_count"],
+        "language": info.get("language", ""),
     }
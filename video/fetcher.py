@@ -65,6 +65,7 @@ def _normalise_info(raw: Dict[str, Any]) -> Dict[str, Any]:
         "stream_url": raw.get("url") or _find_best_url(raw),
         "webpage_url": raw.get("webpage_url", ""),
         "upload_date": raw.get("upload_date", ""),
+        "language": raw.get("language") or "",    # ISO 639-1 when yt-dlp knows it
     }
 
 